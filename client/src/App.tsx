@@ -188,17 +188,27 @@ const App: React.FC = () => {
           const result = await mlsClientRef.current.addMember(mlsGroup, kp);
           if (!result.welcome) continue;
 
-          // Deliver welcome
-          await fetch(`${supabaseUrl}/functions/v1/invite_complete`, {
-            method: 'POST',
-            headers,
-            body: JSON.stringify({
-              invite_id: invite.invite_id,
-              user_id: uid,
-              device_id: did,
-              welcome_hex: result.welcome,
-            }),
-          });
+          // Deliver welcome. On any failure (rejection, network error, non-JSON response)
+          // discard the pending add commit, or every later commit would fail with PendingCommit.
+          let delivered = false;
+          try {
+            const completeRes = await fetch(`${supabaseUrl}/functions/v1/invite_complete`, {
+              method: 'POST',
+              headers,
+              body: JSON.stringify({
+                invite_id: invite.invite_id,
+                user_id: uid,
+                device_id: did,
+                welcome_hex: result.welcome,
+              }),
+            });
+            const completeData = await completeRes.json() as { ok?: boolean };
+            delivered = completeData.ok === true;
+          } finally {
+            if (!delivered) await mlsClientRef.current.discardPendingCommit(mlsGroup);
+          }
+          if (!delivered) continue;
+          await mlsClientRef.current.confirmPendingCommit(mlsGroup);
 
           // Persist updated WASM state
           const stateJson = await mlsClientRef.current.exportState();
@@ -9,6 +9,17 @@ import { saveSentMessage, getSentMessage, getCachedMessage } from '../../utils/m
 import { saveAndSyncWasmState } from '../../utils/wasmStateSync';
 import { ArrowLeft, UserPlus, Users, Lock } from 'lucide-react';
 
+/**
+ * decryptMessage queues incoming proposals (throwing with variant ProposalQueued), and the group
+ * cannot encrypt while any proposal is queued. This app never sends commits to the other members,
+ * so nobody would commit it: drop it instead. Returns whether the error was a queued proposal.
+ */
+async function dropQueuedProposal(mlsClient: MlsClient, mlsGroup: MlsGroup, error: unknown): Promise<boolean> {
+  if ((error as { variant?: string })?.variant !== 'ProposalQueued') return false;
+  await mlsClient.clearPendingProposals(mlsGroup);
+  return true;
+}
+
 interface ChatProps {
   userId: string;
   deviceId: string;
@@ -111,6 +122,7 @@ const Chat: React.FC<ChatProps> = ({
               isSent: m.sender_id === userId,
             });
           } catch (e) {
+            if (await dropQueuedProposal(mlsClient, mlsGroup, e).catch(() => false)) continue;
             if (String(e).includes('CannotDecryptOwnMessage')) {
               const cached = await getSentMessage(groupId, m.server_seq).catch(() => null);
               const text = cached ?? '(your message — text unavailable on this device)';
@@ -197,6 +209,7 @@ const Chat: React.FC<ChatProps> = ({
               });
             });
           } catch (error) {
+            if (await dropQueuedProposal(mlsClient, mlsGroup, error).catch(() => false)) return;
             const errStr = String(error);
             if (errStr.includes('CannotDecryptOwnMessage') || errStr.includes('WrongGroupId')) return;
             console.error('MLS decryption failed:', error);
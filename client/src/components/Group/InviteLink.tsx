@@ -107,19 +107,28 @@ export const InviteLink: React.FC<InviteLinkProps> = ({
         const result = await mlsClient.addMember(mlsGroup, kp);
         if (!result.welcome) throw new Error('Welcome not generated by WASM');
 
-        // Deliver welcome to server
-        const completeRes = await fetch(`${SUPABASE_URL}/functions/v1/invite_complete`, {
-          method: 'POST',
-          headers: authHeaders(),
-          body: JSON.stringify({
-            invite_id: inviteId,
-            user_id: userId,
-            device_id: deviceId,
-            welcome_hex: result.welcome,
-          }),
-        });
-        const completeData = await completeRes.json() as { ok?: boolean; error?: string };
-        if (!completeData.ok) throw new Error(completeData.error ?? 'Failed to complete invite');
+        // Deliver welcome to server. On any failure (rejection, network error, non-JSON
+        // response) discard the pending add commit, or every later commit would fail with PendingCommit.
+        try {
+          const completeRes = await fetch(`${SUPABASE_URL}/functions/v1/invite_complete`, {
+            method: 'POST',
+            headers: authHeaders(),
+            body: JSON.stringify({
+              invite_id: inviteId,
+              user_id: userId,
+              device_id: deviceId,
+              welcome_hex: result.welcome,
+            }),
+          });
+          const completeData = await completeRes.json() as { ok?: boolean; error?: string };
+          if (!completeData.ok) throw new Error(completeData.error ?? 'Failed to complete invite');
+        } catch (e) {
+          await mlsClient.discardPendingCommit(mlsGroup);
+          throw e;
+        }
+
+        // Server accepted the Welcome: merge the pending add commit
+        await mlsClient.confirmPendingCommit(mlsGroup);
 
         // Persist updated WASM state (epoch advanced after addMember)
         const stateJson = await mlsClient.exportState();
@@ -2,17 +2,47 @@
 // RFC 9750 compliant MLS implementation
 import init, {
   create_group,
+  set_signer_from_private_key,
+  export_signer_public_key,
   encrypt,
   decrypt,
   greet,
   generate_key_package,
+  generate_key_packages,
+  list_key_packages,
+  delete_key_package,
+  prune_expired_key_packages,
+  inspect_key_package,
+  set_key_package_policy,
   process_welcome,
   apply_commit,
+  process_message,
   create_update_proposal,
+  leave_group,
+  delete_group,
   add_member,
+  remove_member,
+  self_update_commit,
+  commit_pending_proposals,
+  confirm_pending_commit,
+  discard_pending_commit,
   export_state,
   import_state,
+  export_state_delta,
+  apply_state_delta,
+  use_js_storage,
+  export_state_encrypted,
+  import_state_encrypted,
+  export_group_state,
+  import_group_state,
   load_group,
+  list_stored_groups,
+  load_all_groups,
+  unload_group,
+  list_members,
+  list_pending_proposals,
+  clear_pending_proposals,
+  group_info,
 } from './wasm/pkg/mls_wasm'
 import { decodeBase64Url, encodeBase64Url } from '../utils/crypto'
 
@@ -30,6 +60,11 @@ export interface KeyPackage {
   hpkePublicKey: string; // hex
   credential: string; // hex
   extensions: Record<string, any>;
+  hashRef?: string; // hex KeyPackageRef
+  ciphersuite?: number;
+  lifetime?: { notBefore: number; notAfter: number }; // seconds since epoch
+  capabilities?: MlsMember['capabilities'];
+  lastResort?: boolean;
 }
 
 export interface Commit {
@@ -39,17 +74,169 @@ export interface Commit {
   epochAuthenticator: string; // hex
 }
 
+export interface MlsGroupInfo {
+  groupId: string;
+  epoch: number;
+  treeHash: string; // hex
+  confirmedTranscriptHash: string; // hex
+  epochAuthenticator: string; // hex
+  ciphersuite: number;
+  memberCount: number;
+  ownLeafIndex: number;
+  extensionTypes: number[];
+  hasPendingCommit: boolean;
+}
+
+export interface MlsStoredGroup {
+  groupId: string; // MLS group ID (hex)
+  epoch: number;
+  loaded: boolean; // already loaded in this session
+}
+
+export interface MlsMember {
+  leafIndex: number;
+  identity: string; // hex
+  credentialType: number;
+  signatureKey: string; // hex
+  capabilities: {
+    versions: number[];
+    ciphersuites: number[];
+    extensions: number[];
+    proposals: number[];
+    credentials: number[];
+  };
+  isSelf: boolean;
+}
+
+export interface MlsMessageSender {
+  kind: 'member' | 'external' | 'new_member_proposal' | 'new_member_commit';
+  leafIndex: number | null;
+  identity: string | null; // hex, null if it cannot be resolved
+}
+
+/** A proposal queued for the next commit (listPendingProposals) */
+export interface MlsPendingProposal {
+  proposalRef: string; // hex
+  proposalType: string;
+  sender: MlsMessageSender;
+  subjectIdentity: string | null; // hex identity of the member added, removed or updated
+  removedLeafIndex: number | null;
+  extensionTypes: number[] | null; // set by a group context extensions proposal
+}
+
+/** Result of processMessage, tagged by `type` */
+export type MlsProcessedMessage = {
+  groupId: string;
+  sender: MlsMessageSender;
+  messageEpoch: number; // epoch the message was sent in
+  epoch: number; // group epoch after processing
+} & (
+  | { type: 'application'; plaintext: string | null; data: string } // data: hex
+  | {
+      type: 'commit';
+      epochAuthenticator: string; // hex
+      selfRemoved: boolean;
+      proposalTypes: string[];
+      discardedPendingCommit: boolean; // our own pending commit lost the race
+    }
+  | { type: 'proposal' | 'external_join'; proposalRef: string; proposalType: string }
+)
+
+/** Convert a process_message result into an MlsProcessedMessage */
+function toProcessedMessage(m: any): MlsProcessedMessage {
+  const common = {
+    groupId: m.group_id,
+    sender: { kind: m.sender.kind, leafIndex: m.sender.leaf_index, identity: m.sender.identity },
+    messageEpoch: m.message_epoch,
+    epoch: m.epoch,
+  }
+  switch (m.type) {
+    case 'application':
+      return { ...common, type: m.type, plaintext: m.plaintext, data: m.data }
+    case 'commit':
+      return {
+        ...common,
+        type: m.type,
+        epochAuthenticator: m.epoch_authenticator,
+        selfRemoved: m.self_removed,
+        proposalTypes: m.proposal_types,
+        discardedPendingCommit: m.discarded_pending_commit,
+      }
+    default:
+      return { ...common, type: m.type, proposalRef: m.proposal_ref, proposalType: m.proposal_type }
+  }
+}
+
+/**
+ * Synchronous key/value store that WASM backend storage writes through to (see useStorage).
+ * Keys and values are opaque bytes.
+ */
+export interface MlsStorageBackend {
+  keys(): Uint8Array[];
+  get(key: Uint8Array): Uint8Array | undefined;
+  put(key: Uint8Array, value: Uint8Array): void;
+  delete(key: Uint8Array): void;
+}
+
 export interface Proposal {
   type: 'add' | 'remove' | 'update' | 'psk' | 'reinit';
   data: string; // hex
 }
 
+/** Convert a commit result (add_member, remove_member, ...) into a Commit */
+function toCommit(commitData: any): Commit {
+  return {
+    proposals: commitData.proposals || [],
+    commit: commitData.commit,
+    welcome: commitData.welcome,
+    epochAuthenticator: commitData.epoch_authenticator
+  }
+}
+
+/** Convert a generate_key_package(s) result into a KeyPackage */
+function toKeyPackage(result: unknown): KeyPackage {
+  if (!result || typeof result !== 'object') {
+    throw new Error('Key package generation returned invalid result')
+  }
+  // serde_wasm_bindgen may return a Map instead of plain object
+  const get = (key: string) =>
+    (result as Map<string, unknown>).get?.(key) ?? (result as Record<string, unknown>)[key]
+  const toObj = (v: unknown): Record<string, unknown> =>
+    v == null ? {} : (v instanceof Map ? Object.fromEntries(v) : (v as Record<string, unknown>))
+  const data = get('data')
+  if (typeof data !== 'string' || data.length === 0) {
+    const keys = result instanceof Map ? [...result.keys()] : Object.keys(result)
+    throw new Error(
+      `Key package generation returned missing or empty data (keys: ${keys.join(', ')})`
+    )
+  }
+  return {
+    data,
+    signature: (get('signature') as string) ?? '',
+    hpkePublicKey: (get('hpke_public_key') as string) ?? '',
+    credential: (get('credential') as string) ?? '',
+    extensions: {
+      keyPackage: get('key_package_extensions') ?? [],
+      leafNode: get('leaf_node_extensions') ?? [],
+    },
+    hashRef: get('hash_ref') as string,
+    ciphersuite: get('ciphersuite') as number,
+    lifetime: (() => {
+      const lifetime = toObj(get('lifetime'))
+      return { notBefore: lifetime.not_before as number, notAfter: lifetime.not_after as number }
+    })(),
+    capabilities: toObj(get('capabilities')) as unknown as MlsMember['capabilities'],
+    lastResort: get('last_resort') === true,
+  }
+}
+
 export class MlsClient {
   private credentialIdentity: Uint8Array;
   private wasmInitialized = false;
 
   constructor(userId: string) {
-    // Use userId as credential identity for MLS
+    // Use userId as credential identity for MLS. It also selects this account's signer, so
+    // one MlsClient per account can share the WASM module.
     this.credentialIdentity = new TextEncoder().encode(userId);
   }
 
@@ -60,6 +247,27 @@ export class MlsClient {
     }
   }
 
+  /**
+   * Sign with an existing private key (e.g. mls_sk_enc decrypted with K_enc) instead of a
+   * generated one. Must be called before this account creates groups or key packages.
+   * signatureScheme is an RFC 9420 code point: 0x0807 (Ed25519) or 0x0403 (ECDSA P-256).
+   * Returns the public key, to compare with the stored mls_pk.
+   */
+  async setSignerFromPrivateKey(privateKey: Uint8Array, signatureScheme = 0x0807): Promise<Uint8Array> {
+    await this.init()
+    return set_signer_from_private_key(signatureScheme, privateKey, this.credentialIdentity)
+  }
+
+  /**
+   * Public key this account signs with (the key in its leaf nodes). Read-only: throws NotFound
+   * until the account has a signer (first group / key package, or setSignerFromPrivateKey).
+   * ciphersuite is an optional RFC 9420 code point.
+   */
+  async exportSignerPublicKey(ciphersuite?: number): Promise<Uint8Array> {
+    await this.init()
+    return export_signer_public_key(this.credentialIdentity, ciphersuite)
+  }
+
   async createGroup(groupId: string): Promise<MlsGroup> {
     await this.init()
 
@@ -83,36 +291,98 @@ export class MlsClient {
     }
 
     try {
-      const result = generate_key_package(this.credentialIdentity)
-      if (!result || typeof result !== 'object') {
-        throw new Error('Key package generation returned invalid result')
-      }
-      // serde_wasm_bindgen may return a Map instead of plain object
-      const get = (key: string) =>
-        (result as Map<string, unknown>).get?.(key) ?? (result as Record<string, unknown>)[key]
-      const toObj = (v: unknown): Record<string, unknown> =>
-        v == null ? {} : (v instanceof Map ? Object.fromEntries(v) : (v as Record<string, unknown>))
-      const data = get('data')
-      if (typeof data !== 'string' || data.length === 0) {
-        const keys = result instanceof Map ? [...result.keys()] : Object.keys(result)
-        throw new Error(
-          `Key package generation returned missing or empty data (keys: ${keys.join(', ')})`
-        )
-      }
-      const keyPackage: KeyPackage = {
-        data,
-        signature: (get('signature') as string) ?? '',
-        hpkePublicKey: (get('hpke_public_key') as string) ?? '',
-        credential: (get('credential') as string) ?? '',
-        extensions: toObj(get('extensions'))
-      }
-      return keyPackage
+      return toKeyPackage(generate_key_package(this.credentialIdentity))
     } catch (error) {
       console.error('Failed to generate key package:', error)
-      throw new Error(`MLS key package generation failed: ${error}`)
+      // Rethrow as-is: an MlsError carries the code and variant callers branch on
+      throw error
+    }
+  }
+
+  /**
+   * Generate a batch of one-time key packages for the server pool, optionally followed by
+   * a last-resort key package (lastResort: true) that stays valid after being used.
+   */
+  async generateKeyPackages(
+    count: number,
+    options: { ciphersuite?: number; lifetimeSeconds?: number; lastResort?: boolean } = {}
+  ): Promise<KeyPackage[]> {
+    await this.init()
+
+    if (!this.credentialIdentity?.length) {
+      throw new Error('MLS client credential identity not initialized')
     }
+
+    const result = generate_key_packages(this.credentialIdentity, count, {
+      ciphersuite: options.ciphersuite,
+      lifetime_seconds: options.lifetimeSeconds,
+      last_resort: options.lastResort ?? false,
+    })
+    return (result as unknown[]).map(toKeyPackage)
   }
 
+  /**
+   * Key packages whose private keys this device still holds (unused or last-resort),
+   * for reconciling with the server pool. Entries are the raw WASM descriptions (snake_case).
+   */
+  async listKeyPackages(): Promise<Record<string, unknown>[]> {
+    await this.init()
+    return JSON.parse(list_key_packages())
+  }
+
+  /**
+   * Revoke an unused key package, erasing its private keys.
+   */
+  async deleteKeyPackage(hashRef: string): Promise<void> {
+    await this.init()
+    delete_key_package(hashRef)
+  }
+
+  /**
+   * Erase key packages whose lifetime has ended. Returns the removed hash refs.
+   */
+  async pruneExpiredKeyPackages(now: Date = new Date()): Promise<string[]> {
+    await this.init()
+    return JSON.parse(prune_expired_key_packages(Math.floor(now.getTime() / 1000)))
+  }
+
+  /**
+   * Validate someone else's key package and describe it (identity, signature key, lifetime, ...)
+   * without touching any group, e.g. to show who is joining before confirming an invite.
+   * The result is the raw WASM description (snake_case).
+   */
+  async inspectKeyPackage(keyPackageHex: string): Promise<Record<string, unknown>> {
+    await this.init()
+    const bytes = new Uint8Array((keyPackageHex.match(/../g) ?? []).map((b) => parseInt(b, 16)))
+    return JSON.parse(inspect_key_package(bytes))
+  }
+
+  /**
+   * Rules addMember enforces on key packages for this session (RFC 9420 code points).
+   * Violations throw an MlsError with code PolicyViolation. Pass null to clear the policy.
+   */
+  async setKeyPackagePolicy(policy: {
+    requiredExtensionCapabilities?: number[];
+    requiredProposalCapabilities?: number[];
+    requiredExtensions?: number[];
+    allowedCredentialTypes?: number[];
+    requireIdentityBinding?: boolean;
+  } | null): Promise<void> {
+    await this.init()
+    set_key_package_policy(policy && {
+      required_extension_capabilities: policy.requiredExtensionCapabilities ?? [],
+      required_proposal_capabilities: policy.requiredProposalCapabilities ?? [],
+      required_extensions: policy.requiredExtensions ?? [],
+      allowed_credential_types: policy.allowedCredentialTypes ?? [],
+      require_identity_binding: policy.requireIdentityBinding ?? false,
+    })
+  }
+
+  /**
+   * Commit adding the key package's owner. A key package rejected by the session policy (or for
+   * its lifetime) throws the MlsError unchanged, with code PolicyViolation and a
+   * KeyPackagePolicy::* variant.
+   */
   async addMember(group: MlsGroup, keyPackage: KeyPackage): Promise<Commit> {
     await this.init()
 
@@ -128,17 +398,122 @@ export class MlsClient {
     try {
       // Call real WASM add_member function
       const result = add_member(groupIdHex, keyPackageData)
-      const commitData = JSON.parse(result)
-
-      return {
-        proposals: commitData.proposals || [],
-        commit: commitData.commit,
-        welcome: commitData.welcome,
-        epochAuthenticator: commitData.epoch_authenticator
-      }
+      return toCommit(JSON.parse(result))
     } catch (error) {
       console.error('Failed to add member:', error)
-      throw new Error(`MLS add member failed: ${error}`)
+      throw error
+    }
+  }
+
+  /**
+   * Commit removing every leaf of a member (all of their devices), identified by credential
+   * identity, e.g. new TextEncoder().encode(userId). Queued proposals are committed too.
+   * The commit stays pending until confirmPendingCommit / discardPendingCommit.
+   */
+  async removeMember(group: MlsGroup, credentialIdentity: Uint8Array): Promise<Commit> {
+    await this.init()
+    return toCommit(JSON.parse(remove_member(group.groupId, credentialIdentity)))
+  }
+
+  /**
+   * Commit a path update of our own leaf, rotating its HPKE key (forward secrecy / PCS).
+   * Queued proposals are committed too. The commit stays pending until confirmed or discarded.
+   */
+  async selfUpdateCommit(group: MlsGroup): Promise<Commit> {
+    await this.init()
+    return toCommit(JSON.parse(self_update_commit(group.groupId)))
+  }
+
+  /**
+   * Commit every queued proposal (e.g. from createUpdateProposal, or a member's leaveGroup).
+   * The commit stays pending until confirmed or discarded.
+   */
+  async commitPendingProposals(group: MlsGroup): Promise<Commit> {
+    await this.init()
+    return toCommit(JSON.parse(commit_pending_proposals(group.groupId)))
+  }
+
+  /**
+   * Merge our pending commit (from addMember, removeMember, selfUpdateCommit or
+   * commitPendingProposals) once the server has accepted it.
+   * Until then the group stays in its current epoch and cannot encrypt.
+   */
+  async confirmPendingCommit(group: MlsGroup): Promise<MlsGroup> {
+    await this.init()
+    const groupState = JSON.parse(confirm_pending_commit(group.groupId))
+    return {
+      id: group.id,
+      epoch: groupState.epoch,
+      groupId: groupState.group_id,
+      treeHash: groupState.tree_hash,
+      epochAuthenticator: groupState.epoch_authenticator
+    }
+  }
+
+  /**
+   * Roll back our pending commit (e.g. the server rejected it), staying in the current epoch.
+   */
+  async discardPendingCommit(group: MlsGroup): Promise<void> {
+    await this.init()
+    discard_pending_commit(group.groupId)
+  }
+
+  /**
+   * Members according to the group's ratchet tree (not the server's member list).
+   */
+  async listMembers(group: MlsGroup): Promise<MlsMember[]> {
+    await this.init()
+    const members = JSON.parse(list_members(group.groupId))
+    return members.map((m: any) => ({
+      leafIndex: m.leaf_index,
+      identity: m.identity,
+      credentialType: m.credential_type,
+      signatureKey: m.signature_key,
+      capabilities: m.capabilities,
+      isSelf: m.is_self,
+    }))
+  }
+
+  /**
+   * Proposals queued in the group (received or our own), which the next commit will include.
+   */
+  async listPendingProposals(group: MlsGroup): Promise<MlsPendingProposal[]> {
+    await this.init()
+    return JSON.parse(list_pending_proposals(group.groupId)).map((p: any) => ({
+      proposalRef: p.proposal_ref,
+      proposalType: p.proposal_type,
+      sender: { kind: p.sender.kind, leafIndex: p.sender.leaf_index, identity: p.sender.identity },
+      subjectIdentity: p.subject_identity,
+      removedLeafIndex: p.removed_leaf_index,
+      extensionTypes: p.extension_types,
+    }))
+  }
+
+  /**
+   * Drop every queued proposal, e.g. after rejecting one listed by listPendingProposals.
+   */
+  async clearPendingProposals(group: MlsGroup): Promise<void> {
+    await this.init()
+    clear_pending_proposals(group.groupId)
+  }
+
+  /**
+   * Cryptographic state of the group's current epoch, straight from the WASM module.
+   */
+  async groupInfo(group: MlsGroup): Promise<MlsGroupInfo> {
+    await this.init()
+    const info = JSON.parse(group_info(group.groupId))
+    return {
+      groupId: info.group_id,
+      epoch: info.epoch,
+      treeHash: info.tree_hash,
+      confirmedTranscriptHash: info.confirmed_transcript_hash,
+      epochAuthenticator: info.epoch_authenticator,
+      ciphersuite: info.ciphersuite,
+      memberCount: info.member_count,
+      ownLeafIndex: info.own_leaf_index,
+      extensionTypes: info.extension_types,
+      hasPendingCommit: info.has_pending_commit,
     }
   }
 
@@ -163,14 +538,13 @@ export class MlsClient {
     return encrypt(group.groupId, plaintext)
   }
 
+  /**
+   * Throws the MlsError from WASM unchanged; a received proposal is queued and reported
+   * with variant ProposalQueued rather than being a failed decryption.
+   */
   async decryptMessage(group: MlsGroup, ciphertext: string): Promise<string> {
     await this.init()
-
-    try {
-      return decrypt(group.groupId, ciphertext)
-    } catch (error) {
-      throw new Error(`Decryption failed: ${error}`)
-    }
+    return decrypt(group.groupId, ciphertext)
   }
 
   async applyCommit(group: MlsGroup, commit: Commit): Promise<MlsGroup> {
@@ -188,6 +562,15 @@ export class MlsClient {
     }
   }
 
+  /**
+   * Process any message from the delivery service: application messages are decrypted,
+   * commits merged, and proposals queued for the next commit (see commitPendingProposals).
+   */
+  async processMessage(group: MlsGroup, messageHex: string): Promise<MlsProcessedMessage> {
+    await this.init()
+    return toProcessedMessage(JSON.parse(process_message(group.groupId, messageHex)))
+  }
+
   async updateKeyPackage(): Promise<KeyPackage> {
     await this.init()
 
@@ -203,7 +586,25 @@ export class MlsClient {
   }
 
   /**
-   * Export full WASM state (backend storage + signer) as a JSON string.
+   * Propose removing ourselves from the group; another member must commit it.
+   * Returns the hex-encoded proposal message to send to the delivery service.
+   */
+  async leaveGroup(group: MlsGroup): Promise<string> {
+    await this.init()
+    return leave_group(group.groupId)
+  }
+
+  /**
+   * Delete all local state of a group (memory and WASM storage, secrets zeroized).
+   * Does not notify other members: call leaveGroup first.
+   */
+  async deleteGroup(group: MlsGroup): Promise<void> {
+    await this.init()
+    delete_group(group.groupId)
+  }
+
+  /**
+   * Export full WASM state (backend storage + all signers) as a JSON string.
    * Save this to IndexedDB after important operations for cross-session persistence.
    */
   async exportState(): Promise<string> {
@@ -220,6 +621,79 @@ export class MlsClient {
     import_state(stateJson)
   }
 
+  /**
+   * Persist every WASM storage write through `backend` as it happens, instead of exportState.
+   * Call once after init, before importState, createGroup or loadGroup: it throws InvalidState
+   * once WASM storage holds entries. A legacy importState afterwards is written through.
+   * Opt-in: the backend receives private keys in the clear and must protect them at rest
+   * (openMlsStorageBackend encrypts them with the deriveKWasmState key).
+   */
+  async useStorage(backend: MlsStorageBackend): Promise<void> {
+    await this.init()
+    use_js_storage(backend)
+  }
+
+  /**
+   * Export only the storage changes since the last exportState/importState/exportStateDelta.
+   * Cheap enough to save after every message; apply in order on top of the last full state.
+   */
+  async exportStateDelta(): Promise<string> {
+    await this.init()
+    return export_state_delta()
+  }
+
+  /**
+   * Apply a delta from exportStateDelta after importState (and any earlier deltas), before loadGroup.
+   */
+  async applyStateDelta(deltaJson: string): Promise<void> {
+    await this.init()
+    apply_state_delta(deltaJson)
+  }
+
+  /**
+   * Export full WASM state encrypted under the deriveKWasmState key for the passkey PRF output,
+   * bound to userId, as base64(ciphertext||iv) like encryptString. Safe to store in IndexedDB
+   * or upload to sync_state; private keys never leave WASM in the clear.
+   */
+  async exportStateEncrypted(prfOutput: Uint8Array, userId: string): Promise<string> {
+    await this.init()
+    return export_state_encrypted(prfOutput, userId)
+  }
+
+  /**
+   * Import state written by exportStateEncrypted (or encryptString with deriveKWasmState)
+   * with the same PRF output and userId.
+   */
+  async importStateEncrypted(encrypted: string, prfOutput: Uint8Array, userId: string): Promise<void> {
+    await this.init()
+    import_state_encrypted(encrypted, prfOutput, userId)
+  }
+
+  /**
+   * Export a single group's state (plus the signer of our leaf) for a per-group backup
+   * or moving one conversation to another device. Fails while a commit is pending.
+   */
+  async exportGroupState(group: MlsGroup): Promise<string> {
+    await this.init()
+    return export_group_state(group.groupId)
+  }
+
+  /**
+   * Import a group written by exportGroupState and load it. Other groups are untouched.
+   * The returned id is the MLS group ID; callers map it to their app UUID.
+   */
+  async importGroupState(groupJson: string): Promise<MlsGroup> {
+    await this.init()
+    const groupState = JSON.parse(import_group_state(groupJson))
+    return {
+      id: groupState.group_id,
+      epoch: groupState.epoch,
+      groupId: groupState.group_id,
+      treeHash: groupState.tree_hash,
+      epochAuthenticator: groupState.epoch_authenticator
+    }
+  }
+
   /**
    * Load a previously persisted MLS group from the shared backend's storage.
    * Call after importState. groupIdHex is the MLS group ID (hex) from the stored MlsGroup.
@@ -236,4 +710,40 @@ export class MlsClient {
       epochAuthenticator: groupState.epoch_authenticator,
     }
   }
+
+  /**
+   * Drop a group from WASM memory without deleting it; it is reloaded from storage on next use.
+   * Returns whether it was loaded.
+   */
+  async unloadGroup(group: MlsGroup): Promise<boolean> {
+    await this.init()
+    return unload_group(group.groupId)
+  }
+
+  /**
+   * Groups persisted in WASM storage, found without relying on the app's own metadata.
+   */
+  async listStoredGroups(): Promise<MlsStoredGroup[]> {
+    await this.init()
+    return JSON.parse(list_stored_groups()).map((g: any) => ({
+      groupId: g.group_id,
+      epoch: g.epoch,
+      loaded: g.loaded,
+    }))
+  }
+
+  /**
+   * Load every group persisted in WASM storage into the session.
+   * The returned ids are MLS group IDs; callers map them to their app UUIDs.
+   */
+  async loadAllGroups(): Promise<MlsGroup[]> {
+    await this.init()
+    return JSON.parse(load_all_groups()).map((groupState: any) => ({
+      id: groupState.group_id,
+      epoch: groupState.epoch,
+      groupId: groupState.group_id,
+      treeHash: groupState.tree_hash,
+      epochAuthenticator: groupState.epoch_authenticator
+    }))
+  }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+// src/mls/wasm/src/encryption.rs
+// Passkey-PRF encryption of exported state, matching deriveKWasmState / encryptString in
+// client/src/utils/crypto.ts: K_wasm = HKDF-SHA256(prf_output, "MLS-KDF-Salt",
+// "MLS-WasmState-Encryption"), AES-256-GCM with a random 12-byte IV and the user_id as AAD,
+// stored as base64(ciphertext || iv). Blobs already synced by wasmStateSync.ts decrypt as-is.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64ct::{Base64, Encoding};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::error::{ErrorCode, MlsError};
+
+const KDF_SALT: &[u8] = b"MLS-KDF-Salt";
+// Domain-separated from K_enc ("MLS-PrivateKey-Encryption"), which protects mls_sk_enc
+const KDF_INFO: &[u8] = b"MLS-WasmState-Encryption";
+const PRF_OUTPUT_LEN: usize = 32;
+const IV_LEN: usize = 12;
+
+fn derive_k_wasm_state(prf_output: &[u8]) -> Result<Zeroizing<[u8; 32]>, MlsError> {
+    if prf_output.len() != PRF_OUTPUT_LEN {
+        return Err(MlsError::new(
+            ErrorCode::InvalidInput,
+            format!("PRF output must be {} bytes, got {}", PRF_OUTPUT_LEN, prf_output.len()),
+        ));
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(KDF_SALT), prf_output)
+        .expand(KDF_INFO, key.as_mut())
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Key derivation failed", &e))?;
+    Ok(key)
+}
+
+/// Encrypt `plaintext` under the key derived from the passkey PRF output, bound to `user_id`.
+/// Returns base64(ciphertext || iv), the format of encryptString.
+pub fn encrypt(plaintext: &[u8], prf_output: &[u8], user_id: &str) -> Result<String, MlsError> {
+    let key = derive_k_wasm_state(prf_output)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref())
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Invalid encryption key", &e))?;
+
+    let mut iv = [0u8; IV_LEN];
+    getrandom::getrandom(&mut iv)
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Failed to generate IV", &e))?;
+
+    let mut combined = cipher
+        .encrypt(&Nonce::from(iv), Payload { msg: plaintext, aad: user_id.as_bytes() })
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Encryption failed", &e))?;
+    combined.extend_from_slice(&iv);
+
+    Ok(Base64::encode_string(&combined))
+}
+
+/// Decrypt data produced by `encrypt` or encryptString. Fails with DecryptionFailed for a wrong
+/// passkey or user_id.
+pub fn decrypt(encrypted: &str, prf_output: &[u8], user_id: &str) -> Result<Zeroizing<Vec<u8>>, MlsError> {
+    let combined = Base64::decode_vec(encrypted.trim())
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid encrypted state base64", &e))?;
+    if combined.len() < IV_LEN {
+        return Err(MlsError::new(ErrorCode::InvalidInput, "Encrypted state is too short"));
+    }
+    let (ciphertext, iv) = combined.split_at(combined.len() - IV_LEN);
+    let iv: [u8; IV_LEN] = iv.try_into()
+        .map_err(|_| MlsError::new(ErrorCode::InvalidInput, format!("IV must be {} bytes", IV_LEN)))?;
+
+    let key = derive_k_wasm_state(prf_output)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref())
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Invalid encryption key", &e))?;
+
+    cipher
+        .decrypt(&Nonce::from(iv), Payload { msg: ciphertext, aad: user_id.as_bytes() })
+        .map(Zeroizing::new)
+        .map_err(|_| MlsError::new(
+            ErrorCode::DecryptionFailed,
+            "State decryption failed (wrong passkey or user_id, or tampered data)",
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRF: [u8; 32] = [7; 32];
+
+    #[test]
+    fn decrypts_encrypt_string_blob() {
+        // encryptString('{"hello":1}', await deriveKWasmState(PRF), "u1") in the browser
+        let blob = "r/RvMOnHSRDZ9gORcPic+iaFPkW9VKwGpgBFt90AEwOk7T6XGKVh";
+        let plaintext = decrypt(blob, &PRF, "u1").unwrap();
+        assert_eq!(plaintext.as_slice(), br#"{"hello":1}"#);
+    }
+
+    #[test]
+    fn round_trip_is_bound_to_user_id() {
+        let blob = encrypt(b"state", &PRF, "u1").unwrap();
+        assert_eq!(decrypt(&blob, &PRF, "u1").unwrap().as_slice(), b"state");
+        assert_eq!(decrypt(&blob, &PRF, "u2").unwrap_err().code, ErrorCode::DecryptionFailed);
+        assert_eq!(decrypt(&blob, &[8; 32], "u1").unwrap_err().code, ErrorCode::DecryptionFailed);
+    }
+}
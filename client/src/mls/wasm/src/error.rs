@@ -0,0 +1,139 @@
+// src/mls/wasm/src/error.rs
+// Typed errors surfaced to JS by every export
+
+use std::fmt::Debug;
+
+use wasm_bindgen::prelude::*;
+
+/// Stable error codes exposed to JS as `ErrorCode`.
+/// Discriminants are part of the JS API: never renumber, only append.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The group is neither loaded in this WASM session nor in backend storage
+    GroupNotFound = 1,
+    /// The message belongs to a different epoch than the group
+    WrongEpoch = 2,
+    /// The message could not be decrypted (including our own sent messages)
+    DecryptionFailed = 3,
+    /// Malformed hex, TLS encoding, JSON or an unexpected message type
+    InvalidInput = 4,
+    /// Reading or writing the backend storage failed
+    StorageError = 5,
+    /// A key package, commit or proposal failed MLS validation
+    ValidationFailed = 6,
+    /// The group is in the wrong state (pending commit, evicted, ...)
+    InvalidState = 7,
+    /// A member, proposal or other referenced item does not exist
+    NotFound = 8,
+    /// Key generation, hashing or signing failed
+    CryptoError = 9,
+    /// Serialization or OpenMLS library error that should not happen
+    Internal = 10,
+    /// A key package was rejected by the configured acceptance policy
+    PolicyViolation = 11,
+    /// Saved state comes from a newer build or an unsupported OpenMLS storage version
+    IncompatibleState = 12,
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const MLS_ERROR_TS: &'static str = r#"
+/** Error thrown by every mls_wasm export. */
+export interface MlsError extends Error {
+  name: "MlsError";
+  code: ErrorCode;
+  /** OpenMLS error variant path, e.g. "ValidationError::WrongEpoch" */
+  variant?: string;
+}
+"#;
+
+/// Error returned by every export. Converted into a JS `Error` named "MlsError"
+/// carrying `code` and, when it wraps an OpenMLS error, the `variant` name.
+#[derive(Debug, Clone)]
+pub struct MlsError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub variant: Option<String>,
+}
+
+impl MlsError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), variant: None }
+    }
+
+    /// Error with an explicit variant name, for failures raised by this module rather than OpenMLS
+    pub fn with_variant(code: ErrorCode, variant: &str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), variant: Some(variant.to_string()) }
+    }
+
+    /// Wrap an underlying error. `code` is the default for this call site; well-known
+    /// OpenMLS variants (wrong epoch, decryption, storage, group state) override it.
+    pub fn wrap<E: Debug>(code: ErrorCode, context: &str, err: &E) -> Self {
+        let debug = format!("{:?}", err);
+        let variant = variant_path(&debug);
+        Self {
+            code: refine_code(code, &variant),
+            message: format!("{}: {}", context, debug),
+            variant: Some(variant).filter(|v| !v.is_empty()),
+        }
+    }
+}
+
+impl std::fmt::Display for MlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<MlsError> for JsValue {
+    fn from(err: MlsError) -> Self {
+        let js_err = js_sys::Error::new(&err.message);
+        js_err.set_name("MlsError");
+        // Reflect::set only fails on frozen/non-object targets, which a fresh Error is not
+        let _ = js_sys::Reflect::set(&js_err, &"code".into(), &JsValue::from(err.code));
+        if let Some(variant) = err.variant {
+            let _ = js_sys::Reflect::set(&js_err, &"variant".into(), &variant.into());
+        }
+        js_err.into()
+    }
+}
+
+/// Extract the nested enum variant path from a Debug string,
+/// e.g. `ValidationError(UnableToDecrypt(AeadError))` -> `ValidationError::UnableToDecrypt::AeadError`.
+fn variant_path(debug: &str) -> String {
+    let mut segments = Vec::new();
+    let mut rest = debug;
+    loop {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let ident = &rest[..end];
+        if !ident.starts_with(|c: char| c.is_ascii_uppercase()) {
+            break;
+        }
+        segments.push(ident);
+        match rest[end..].strip_prefix('(') {
+            Some(inner) => rest = inner,
+            None => break,
+        }
+    }
+    segments.join("::")
+}
+
+fn refine_code(default: ErrorCode, variant: &str) -> ErrorCode {
+    let has = |name: &str| variant.split("::").any(|segment| segment == name);
+
+    if has("WrongEpoch") {
+        ErrorCode::WrongEpoch
+    } else if has("UnableToDecrypt") || has("CannotDecryptOwnMessage") || has("AeadError") {
+        ErrorCode::DecryptionFailed
+    } else if has("StorageError") || has("KeyStoreError") {
+        ErrorCode::StorageError
+    } else if has("UseAfterEviction") || has("PendingCommit") || has("NoPendingCommit") || has("PendingProposal") {
+        ErrorCode::InvalidState
+    } else if has("LibraryError") {
+        ErrorCode::Internal
+    } else {
+        default
+    }
+}
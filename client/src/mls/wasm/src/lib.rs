@@ -1,20 +1,26 @@
 // src/mls/wasm/src/lib.rs
 // Real MLS implementation using OpenMLS 0.7
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use hex;
 use openmls::prelude::*;
+use openmls::ciphersuite::hash_ref::ProposalRef;
 use openmls::prelude::tls_codec::{Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait};
 use openmls_traits::OpenMlsProvider;
+use openmls_traits::storage::StorageProvider;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+mod error;
 mod storage;
 mod provider;
+mod policy;
+mod encryption;
+mod state;
 
-use storage::{get_or_create_signer, store_group, take_group, store_key_package};
-use provider::BACKEND;
+use error::{ErrorCode, MlsError};
+use storage::{get_or_create_signer, group_signer, store_group, take_group};
+use provider::{JsStore, MlsProvider, StorageDelta, BACKEND};
 
 #[wasm_bindgen]
 extern "C" {
@@ -32,7 +38,53 @@ pub struct MlsGroupState {
     group_id: String,
     epoch: u64,
     tree_hash: String,
+    confirmed_transcript_hash: String,
     epoch_authenticator: String,
+    /// RFC 9420 ciphersuite code point
+    ciphersuite: u16,
+    member_count: u32,
+    own_leaf_index: u32,
+    /// Types of the group context extensions
+    extension_types: Vec<u16>,
+    /// Hex TLS-serialized group context extensions
+    extensions: String,
+    /// Whether one of our commits is waiting for confirm_pending_commit / discard_pending_commit
+    has_pending_commit: bool,
+}
+
+impl MlsGroupState {
+    /// Snapshot of the group's current (merged) epoch.
+    /// OpenMLS 0.7 only exposes the group context accessor to its own tests, so the
+    /// context is read back from backend storage, where it is written on every merge.
+    fn from_group(group: &MlsGroup) -> Result<Self, MlsError> {
+        let context: GroupContext = BACKEND.with(|b| {
+            b.borrow().storage().group_context(group.group_id())
+        })
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to read group context", &e))?
+            .ok_or_else(|| MlsError::new(ErrorCode::StorageError, "Group context missing from storage"))?;
+        let extensions = group.extensions();
+
+        Ok(MlsGroupState {
+            group_id: hex::encode(group.group_id().as_slice()),
+            epoch: group.epoch().as_u64(),
+            tree_hash: hex::encode(context.tree_hash()),
+            confirmed_transcript_hash: hex::encode(context.confirmed_transcript_hash()),
+            epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
+            ciphersuite: group.ciphersuite() as u16,
+            member_count: group.members().count() as u32,
+            own_leaf_index: group.own_leaf_index().u32(),
+            extension_types: extensions.iter().map(|e| e.extension_type().into()).collect(),
+            extensions: hex::encode(extensions.tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Extensions serialization error", &e))?),
+            has_pending_commit: group.pending_commit().is_some(),
+        })
+    }
+
+    /// `MlsGroupState` JSON for the group, as returned by the state-changing exports
+    fn json(group: &MlsGroup) -> Result<String, MlsError> {
+        serde_json::to_string(&Self::from_group(group)?)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -44,15 +96,135 @@ pub struct CommitOutput {
     epoch_authenticator: String,
 }
 
-/// Create a new MLS group
+/// Binary form of `CommitOutput`, returned by the `*_bytes` exports.
+/// Byte fields are exposed to JS as `Uint8Array`.
+#[wasm_bindgen]
+pub struct CommitBytes {
+    proposals: Vec<String>,
+    commit: Vec<u8>,
+    welcome: Option<Vec<u8>>,
+    epoch_authenticator: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl CommitBytes {
+    /// Hex-encoded refs of the proposals covered by the commit
+    #[wasm_bindgen(getter)]
+    pub fn proposals(&self) -> Vec<String> {
+        self.proposals.clone()
+    }
+
+    /// TLS-serialized MlsMessage carrying the commit
+    #[wasm_bindgen(getter)]
+    pub fn commit(&self) -> Vec<u8> {
+        self.commit.clone()
+    }
+
+    /// TLS-serialized MlsMessage carrying the Welcome, if the commit added members
+    #[wasm_bindgen(getter)]
+    pub fn welcome(&self) -> Option<Vec<u8>> {
+        self.welcome.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn epoch_authenticator(&self) -> Vec<u8> {
+        self.epoch_authenticator.clone()
+    }
+}
+
+impl CommitBytes {
+    /// Hex `CommitOutput` JSON, as returned by the string exports
+    fn to_json(&self) -> Result<String, MlsError> {
+        let output = CommitOutput {
+            proposals: self.proposals.clone(),
+            commit: hex::encode(&self.commit),
+            welcome: self.welcome.as_ref().map(hex::encode),
+            epoch_authenticator: hex::encode(&self.epoch_authenticator),
+        };
+
+        serde_json::to_string(&output)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    }
+}
+
+/// Serialize a commit (and optional Welcome) into `CommitBytes`.
+/// `proposals` lists the hex proposal refs covered by the commit.
+fn commit_bytes(
+    group: &MlsGroup,
+    proposals: Vec<String>,
+    commit: &MlsMessageOut,
+    welcome: Option<&MlsMessageOut>,
+) -> Result<CommitBytes, MlsError> {
+    let welcome = welcome
+        .map(|w| w.tls_serialize_detached()
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Welcome serialization error", &e)))
+        .transpose()?;
+
+    // The commit is still pending, so report the authenticator of the epoch it will create
+    let epoch_authenticator = group.pending_commit()
+        .and_then(|c| c.epoch_authenticator())
+        .unwrap_or_else(|| group.epoch_authenticator());
+
+    Ok(CommitBytes {
+        proposals,
+        commit: commit.tls_serialize_detached()
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Commit serialization error", &e))?,
+        welcome,
+        epoch_authenticator: epoch_authenticator.as_slice().to_vec(),
+    })
+}
+
+/// Serialize a commit (and optional Welcome) into the `CommitOutput` JSON returned to JS.
+fn commit_output(
+    group: &MlsGroup,
+    proposals: Vec<String>,
+    commit: &MlsMessageOut,
+    welcome: Option<&MlsMessageOut>,
+) -> Result<String, MlsError> {
+    commit_bytes(group, proposals, commit, welcome)?.to_json()
+}
+
+/// Ciphersuites this module can create groups and key packages for, in preference order.
+/// All of them are advertised in our leaf node capabilities.
+const SUPPORTED_CIPHERSUITES: [Ciphersuite; 3] = [
+    Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519,
+    Ciphersuite::MLS_128_DHKEMP256_AES128GCM_SHA256_P256,
+    Ciphersuite::MLS_128_DHKEMX25519_CHACHA20POLY1305_SHA256_Ed25519,
+];
+
+/// Resolve an RFC 9420 ciphersuite code point (e.g. 0x0001, 0x0002, 0x0003) passed from JS.
+/// `None` selects the default suite, MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519.
+fn resolve_ciphersuite(ciphersuite: Option<u16>) -> Result<Ciphersuite, MlsError> {
+    let Some(id) = ciphersuite else {
+        return Ok(SUPPORTED_CIPHERSUITES[0]);
+    };
+    Ciphersuite::try_from(id)
+        .ok()
+        .filter(|cs| SUPPORTED_CIPHERSUITES.contains(cs))
+        .ok_or_else(|| MlsError::new(ErrorCode::InvalidInput, format!("Unsupported ciphersuite: 0x{:04x}", id)))
+}
+
+/// Leaf node capabilities advertising every supported ciphersuite and the last_resort
+/// extension (peers reject key packages carrying extensions their leaf does not list)
+fn leaf_capabilities() -> Capabilities {
+    Capabilities::builder()
+        .ciphersuites(SUPPORTED_CIPHERSUITES.to_vec())
+        .extensions(vec![ExtensionType::LastResort])
+        .build()
+}
+
+/// Create a new MLS group.
+/// `credential_identity` selects the signer: each identity has its own keypair per signature
+/// scheme, created on first use, so several accounts can share this WASM instance.
+/// `ciphersuite` is an optional RFC 9420 code point (see resolve_ciphersuite).
 #[wasm_bindgen]
-pub fn create_group(credential_identity: &[u8]) -> Result<String, JsValue> {
+pub fn create_group(credential_identity: &[u8], ciphersuite: Option<u16>) -> Result<String, MlsError> {
+    let ciphersuite = resolve_ciphersuite(ciphersuite)?;
+
     BACKEND.with(|b| {
         let backend = b.borrow();
-        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
 
-        let signer = get_or_create_signer(ciphersuite)
-            .map_err(|e| JsValue::from_str(&e))?;
+        let signer = get_or_create_signer(credential_identity, ciphersuite)?;
 
         let credential = BasicCredential::new(credential_identity.to_vec());
         let credential_with_key = CredentialWithKey {
@@ -60,132 +232,522 @@ pub fn create_group(credential_identity: &[u8]) -> Result<String, JsValue> {
             signature_key: signer.public().into(),
         };
 
-        let group_config = MlsGroupCreateConfig::default();
+        let group_config = MlsGroupCreateConfig::builder()
+            .ciphersuite(ciphersuite)
+            .capabilities(leaf_capabilities())
+            // process_welcome is given no tree: joiners read it from the Welcome's extension
+            .use_ratchet_tree_extension(true)
+            .build();
         let group = MlsGroup::new(
             &*backend,
             &signer,
             &group_config,
             credential_with_key,
-        ).map_err(|e| JsValue::from_str(&format!("Failed to create group: {:?}", e)))?;
+        ).map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Failed to create group", &e))?;
 
         let group_id = group.group_id().as_slice().to_vec();
 
-        let state = MlsGroupState {
-            group_id: hex::encode(&group_id),
-            epoch: group.epoch().as_u64(),
-            tree_hash: hex::encode(&group_id),
-            epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
-        };
+        let state = MlsGroupState::json(&group);
 
         store_group(group_id, group);
 
-        serde_json::to_string(&state)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+        state
+    })
+}
+
+/// Use an existing signing key instead of generating one, e.g. the device's `mls_sk_enc` after
+/// decryption with the passkey-derived K_enc (see spec/identity_and_passkeys.md).
+/// `signature_scheme` is an RFC 9420 code point: 0x0807 (Ed25519) or 0x0403 (ECDSA P-256), with
+/// the raw 32-byte private key. The signer is registered for `credential_identity`, which defaults
+/// to the key's user_id, `base64url(SHA256(public_key))`. Returns the public key.
+/// Call before create_group / generate_key_package for that identity; fails with InvalidState if
+/// the identity already has a different signer for the scheme.
+#[wasm_bindgen]
+pub fn set_signer_from_private_key(
+    signature_scheme: u16,
+    private_key: &[u8],
+    credential_identity: Option<Vec<u8>>,
+) -> Result<Vec<u8>, MlsError> {
+    let scheme = SignatureScheme::try_from(signature_scheme)
+        .ok()
+        .filter(|scheme| SUPPORTED_CIPHERSUITES.iter().any(|cs| cs.signature_algorithm() == *scheme))
+        .ok_or_else(|| MlsError::new(ErrorCode::InvalidInput, format!("Unsupported signature scheme: 0x{:04x}", signature_scheme)))?;
+
+    let public_key = storage::derive_public_key(scheme, private_key)?;
+    let identity = credential_identity.unwrap_or_else(|| policy::user_id(&public_key).into_bytes());
+    storage::import_signer(identity, scheme, private_key, public_key.clone())?;
+    Ok(public_key)
+}
+
+/// Public key of the signer `credential_identity` uses for `ciphersuite` (optional RFC 9420 code
+/// point, see resolve_ciphersuite): the key in its leaf nodes, to publish as the device's `mls_pk`
+/// or compare with it. Read-only: fails with NotFound if the identity has no signer for the scheme
+/// yet (it is created by create_group / generate_key_package, or set_signer_from_private_key).
+#[wasm_bindgen]
+pub fn export_signer_public_key(credential_identity: &[u8], ciphersuite: Option<u16>) -> Result<Vec<u8>, MlsError> {
+    let scheme = resolve_ciphersuite(ciphersuite)?.signature_algorithm();
+    storage::signer_public_key(credential_identity, scheme)
+        .ok_or_else(|| MlsError::new(ErrorCode::NotFound, "No signer registered for this identity and signature scheme"))
+}
+
+/// Default key package lifetime: 4 weeks
+const DEFAULT_KEY_PACKAGE_LIFETIME_SECONDS: u32 = 4 * 7 * 24 * 60 * 60;
+
+/// Longest lifetime OpenMLS peers accept for a key package: 12 weeks
+/// (their limit also allows for the hour Lifetime::new backdates not_before by)
+const MAX_KEY_PACKAGE_LIFETIME_SECONDS: u32 = 12 * 7 * 24 * 60 * 60;
+
+/// Key package lifetime window, in seconds since the Unix epoch
+#[derive(Serialize)]
+struct LifetimeWindow {
+    not_before: u64,
+    not_after: u64,
+}
+
+/// Description of a KeyPackage, derived from the package itself
+#[derive(Serialize)]
+struct KeyPackageInfo {
+    /// Hex KeyPackageRef, the identifier used by the delivery service and our key package store
+    hash_ref: String,
+    /// Hex credential identity
+    identity: String,
+    credential_type: u16,
+    /// Hex signature public key
+    signature_key: String,
+    /// RFC 9420 ciphersuite code point
+    ciphersuite: u16,
+    lifetime: LifetimeWindow,
+    capabilities: MemberCapabilities,
+    /// Types of the key package extensions
+    key_package_extensions: Vec<u16>,
+    /// Types of the leaf node extensions
+    leaf_node_extensions: Vec<u16>,
+    /// Whether the package carries the last_resort extension (RFC 9420), i.e. may be used more than once
+    last_resort: bool,
+}
+
+/// Output of generate_key_package: the serialized package plus its real contents
+#[derive(Serialize)]
+struct GeneratedKeyPackage {
+    /// Hex TLS-serialized KeyPackage
+    data: String,
+    /// Hex TLS-serialized leaf node signature
+    signature: String,
+    /// Hex TLS-serialized leaf node HPKE encryption key
+    hpke_public_key: String,
+    /// Hex credential identity
+    credential: String,
+    #[serde(flatten)]
+    info: KeyPackageInfo,
+}
+
+/// Resolve a key package lifetime in seconds passed from JS (None selects the 4-week default)
+fn resolve_lifetime(lifetime_seconds: Option<u32>) -> Result<Lifetime, MlsError> {
+    let seconds = lifetime_seconds.unwrap_or(DEFAULT_KEY_PACKAGE_LIFETIME_SECONDS);
+    if seconds == 0 || seconds > MAX_KEY_PACKAGE_LIFETIME_SECONDS {
+        return Err(MlsError::new(
+            ErrorCode::InvalidInput,
+            format!("Key package lifetime must be between 1 and {} seconds", MAX_KEY_PACKAGE_LIFETIME_SECONDS),
+        ));
+    }
+    Ok(Lifetime::new(seconds as u64))
+}
+
+/// Generate a key package for joining groups.
+/// `ciphersuite` is an optional RFC 9420 code point (see resolve_ciphersuite);
+/// `lifetime_seconds` defaults to 4 weeks and may be at most 12 weeks.
+#[wasm_bindgen]
+pub fn generate_key_package(
+    credential_identity: &[u8],
+    ciphersuite: Option<u16>,
+    lifetime_seconds: Option<u32>,
+) -> Result<JsValue, MlsError> {
+    let bundle = build_key_package(
+        credential_identity,
+        resolve_ciphersuite(ciphersuite)?,
+        resolve_lifetime(lifetime_seconds)?,
+        false,
+    )?;
+
+    let output = BACKEND.with(|b| generated_key_package(bundle.key_package(), b.borrow().crypto()))?;
+
+    serde_wasm_bindgen::to_value(&output)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+}
+
+fn generated_key_package(
+    key_package: &KeyPackage,
+    crypto: &impl OpenMlsCrypto,
+) -> Result<GeneratedKeyPackage, MlsError> {
+    let leaf_node = key_package.leaf_node();
+
+    Ok(GeneratedKeyPackage {
+        data: hex::encode(
+            key_package
+                .tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))?
+        ),
+        signature: hex::encode(
+            leaf_node.signature()
+                .tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Signature serialization error", &e))?
+        ),
+        hpke_public_key: hex::encode(
+            leaf_node.encryption_key()
+                .tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Public key serialization error", &e))?
+        ),
+        credential: hex::encode(leaf_node.credential().serialized_content()),
+        info: describe_key_package(key_package, crypto)?,
     })
 }
 
-/// Generate a key package for joining groups
+fn describe_key_package(key_package: &KeyPackage, crypto: &impl OpenMlsCrypto) -> Result<KeyPackageInfo, MlsError> {
+    let leaf_node = key_package.leaf_node();
+    let hash_ref = key_package.hash_ref(crypto)
+        .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Failed to compute hash ref", &e))?;
+
+    Ok(KeyPackageInfo {
+        hash_ref: hex::encode(hash_ref.as_slice()),
+        identity: hex::encode(leaf_node.credential().serialized_content()),
+        credential_type: leaf_node.credential().credential_type().into(),
+        signature_key: hex::encode(leaf_node.signature_key().as_slice()),
+        ciphersuite: key_package.ciphersuite() as u16,
+        lifetime: LifetimeWindow {
+            not_before: key_package.life_time().not_before(),
+            not_after: key_package.life_time().not_after(),
+        },
+        capabilities: member_capabilities(leaf_node.capabilities())?,
+        key_package_extensions: key_package.extensions().iter().map(|e| e.extension_type().into()).collect(),
+        leaf_node_extensions: leaf_node.extensions().iter().map(|e| e.extension_type().into()).collect(),
+        last_resort: key_package.last_resort(),
+    })
+}
+
+/// Most key packages generate_key_packages creates in one call
+const MAX_KEY_PACKAGE_BATCH: u32 = 100;
+
+/// Options accepted by generate_key_packages
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeyPackageOptions {
+    /// RFC 9420 ciphersuite code point (see resolve_ciphersuite)
+    ciphersuite: Option<u16>,
+    /// Lifetime in seconds (see resolve_lifetime)
+    lifetime_seconds: Option<u32>,
+    /// Also create a last-resort key package, which stays usable after a Welcome consumes it
+    last_resort: bool,
+}
+
+/// Generate `count` one-time key packages for the server's key package pool.
+/// `options` is `{ ciphersuite?, lifetime_seconds?, last_resort? }`; with `last_resort` set,
+/// one extra key package carrying the last_resort extension is appended to the result.
+/// Returns an array of generate_key_package outputs, each with its `hash_ref` and `last_resort` flag.
+#[wasm_bindgen]
+pub fn generate_key_packages(credential_identity: &[u8], count: u32, options: JsValue) -> Result<JsValue, MlsError> {
+    let options: KeyPackageOptions = if options.is_undefined() || options.is_null() {
+        KeyPackageOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid key package options", &e))?
+    };
+
+    let output = key_package_batch(credential_identity, count, &options)?;
+
+    serde_wasm_bindgen::to_value(&output)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+}
+
+fn key_package_batch(
+    credential_identity: &[u8],
+    count: u32,
+    options: &KeyPackageOptions,
+) -> Result<Vec<GeneratedKeyPackage>, MlsError> {
+    if count > MAX_KEY_PACKAGE_BATCH {
+        return Err(MlsError::new(
+            ErrorCode::InvalidInput,
+            format!("At most {} key packages can be generated at once", MAX_KEY_PACKAGE_BATCH),
+        ));
+    }
+    let ciphersuite = resolve_ciphersuite(options.ciphersuite)?;
+
+    (0..count)
+        .map(|_| false)
+        .chain(options.last_resort.then_some(true))
+        .map(|last_resort| {
+            // Each package gets its own Lifetime so not_before tracks its creation time
+            let bundle = build_key_package(credential_identity, ciphersuite, resolve_lifetime(options.lifetime_seconds)?, last_resort)?;
+            BACKEND.with(|b| generated_key_package(bundle.key_package(), b.borrow().crypto()))
+        })
+        .collect()
+}
+
+/// Generate a key package and return it as TLS-serialized KeyPackage bytes
 #[wasm_bindgen]
-pub fn generate_key_package(credential_identity: &[u8]) -> Result<JsValue, JsValue> {
+pub fn generate_key_package_bytes(
+    credential_identity: &[u8],
+    ciphersuite: Option<u16>,
+    lifetime_seconds: Option<u32>,
+) -> Result<Vec<u8>, MlsError> {
+    build_key_package(
+        credential_identity,
+        resolve_ciphersuite(ciphersuite)?,
+        resolve_lifetime(lifetime_seconds)?,
+        false,
+    )?
+        .key_package()
+        .tls_serialize_detached()
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+}
+
+/// Build a key package bundle, keeping its private keys in backend storage
+fn build_key_package(
+    credential_identity: &[u8],
+    ciphersuite: Ciphersuite,
+    lifetime: Lifetime,
+    last_resort: bool,
+) -> Result<KeyPackageBundle, MlsError> {
     BACKEND.with(|b| {
         let backend = b.borrow();
-        let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
 
-        let signer = get_or_create_signer(ciphersuite)
-            .map_err(|e| JsValue::from_str(&e))?;
+        let signer = get_or_create_signer(credential_identity, ciphersuite)?;
 
         let credential = BasicCredential::new(credential_identity.to_vec());
         let credential_with_key = CredentialWithKey {
-            credential: credential.clone().into(),
+            credential: credential.into(),
             signature_key: signer.public().into(),
         };
 
-        let key_package = KeyPackage::builder()
+        let mut builder = KeyPackage::builder()
+            .leaf_node_capabilities(leaf_capabilities())
+            .key_package_lifetime(lifetime);
+        if last_resort {
+            builder = builder.mark_as_last_resort();
+        }
+        // build() also writes the bundle to backend storage under its hash ref
+        builder
             .build(ciphersuite, &*backend, &signer, credential_with_key)
-            .map_err(|e| JsValue::from_str(&format!("Failed to build key package: {:?}", e)))?;
-
-        let hash_ref = key_package.key_package().hash_ref(backend.crypto())
-            .map_err(|e| JsValue::from_str(&format!("Failed to compute hash ref: {:?}", e)))?;
-        store_key_package(hash_ref.as_slice().to_vec(), key_package.clone());
-
-        let leaf_node = key_package.key_package().leaf_node();
-        let output = serde_json::json!({
-            "data": hex::encode(
-                key_package
-                    .key_package()
-                    .tls_serialize_detached()
-                    .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))?
-            ),
-            "signature": hex::encode(
-                leaf_node.signature()
-                    .tls_serialize_detached()
-                    .map_err(|e| JsValue::from_str(&format!("Signature serialization error: {:?}", e)))?
-            ),
-            "hpke_public_key": hex::encode(
-                leaf_node.encryption_key()
-                    .tls_serialize_detached()
-                    .map_err(|e| JsValue::from_str(&format!("Public key serialization error: {:?}", e)))?
-            ),
-            "credential": hex::encode(credential.identity()),
-            "extensions": {
-                "capabilities": {
-                    "versions": ["1.0"],
-                    "cipher_suites": ["MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519"],
-                    "extensions": ["application_id", "ratchet_tree"]
-                },
-                "lifetime": {
-                    "not_before": 0,
-                    "not_after": 7 * 24 * 60 * 60 * 1000u64
-                }
-            }
+            .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Failed to build key package", &e))
+    })
+}
+
+/// Key packages whose private keys are still held in backend storage: generated but not yet
+/// consumed by a Welcome, plus last-resort packages. Returns a JSON array of key package
+/// descriptions (hash_ref, lifetime, last_resort, ...) ordered by not_before, so the
+/// device can reconcile them with the server's key package pool.
+#[wasm_bindgen]
+pub fn list_key_packages() -> Result<String, MlsError> {
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut key_packages = key_package_inventory(&backend)?
+            .into_iter()
+            .map(|(_, info)| info)
+            .collect::<Vec<_>>();
+        key_packages.sort_by(|a, b| {
+            (a.lifetime.not_before, &a.hash_ref).cmp(&(b.lifetime.not_before, &b.hash_ref))
         });
 
-        serde_wasm_bindgen::to_value(&output)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+        serde_json::to_string(&key_packages)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    })
+}
+
+/// Revoke an unused key package (by hex hash_ref), erasing its HPKE private keys
+/// so a Welcome for it can no longer be processed.
+#[wasm_bindgen]
+pub fn delete_key_package(hash_ref_hex: &str) -> Result<(), MlsError> {
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let (storage_key, _) = key_package_inventory(&backend)?
+            .into_iter()
+            .find(|(_, info)| info.hash_ref.eq_ignore_ascii_case(hash_ref_hex))
+            .ok_or_else(|| MlsError::new(ErrorCode::NotFound, "Key package not found"))?;
+
+        storage::erase_storage_value(backend.storage(), &storage_key)
+    })
+}
+
+/// Erase every key package whose lifetime has ended by `now_seconds` (Unix time in seconds,
+/// e.g. `Math.floor(Date.now() / 1000)`), last-resort ones included.
+/// Returns a JSON array of the hex hash_refs that were removed.
+#[wasm_bindgen]
+pub fn prune_expired_key_packages(now_seconds: u32) -> Result<String, MlsError> {
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut pruned = Vec::new();
+        for (storage_key, info) in key_package_inventory(&backend)? {
+            if info.lifetime.not_after <= now_seconds as u64 {
+                storage::erase_storage_value(backend.storage(), &storage_key)?;
+                pruned.push(info.hash_ref);
+            }
+        }
+
+        serde_json::to_string(&pruned)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
     })
 }
 
-/// Add a member to the group
+/// Key packages in backend storage with their storage keys and descriptions
+fn key_package_inventory(backend: &MlsProvider) -> Result<Vec<(Vec<u8>, KeyPackageInfo)>, MlsError> {
+    storage::stored_key_packages(backend.storage())?
+        .into_iter()
+        .map(|(storage_key, bundle)| Ok((storage_key, describe_key_package(bundle.key_package(), backend.crypto())?)))
+        .collect()
+}
+
+/// Add a member to the group. Queued proposals are committed too and listed in `proposals`.
+/// The commit is left pending until confirm_pending_commit (or discard_pending_commit) is called.
 #[wasm_bindgen]
-pub fn add_member(group_id_hex: &str, key_package_hex: &str) -> Result<String, JsValue> {
+pub fn add_member(group_id_hex: &str, key_package_hex: &str) -> Result<String, MlsError> {
     let group_id = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let kp_bytes = hex::decode(key_package_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid key package hex", &e))?;
+
+    add_member_commit(&group_id, &kp_bytes)?.to_json()
+}
+
+/// Binary variant of add_member: raw group ID and TLS-serialized KeyPackage in, `CommitBytes` out
+#[wasm_bindgen]
+pub fn add_member_bytes(group_id: &[u8], key_package: &[u8]) -> Result<CommitBytes, MlsError> {
+    add_member_commit(group_id, key_package)
+}
 
+fn add_member_commit(group_id: &[u8], kp_bytes: &[u8]) -> Result<CommitBytes, MlsError> {
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let mut group = take_group(&group_id)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
+        let mut group = take_group(group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-        let result = (|| -> Result<String, JsValue> {
-            let kp_bytes = hex::decode(key_package_hex)
-                .map_err(|e| JsValue::from_str(&format!("Invalid key package hex: {:?}", e)))?;
-            let key_package_in = KeyPackageIn::tls_deserialize(&mut kp_bytes.as_slice())
-                .map_err(|e| JsValue::from_str(&format!("Invalid key package: {:?}", e)))?;
+        let result = (|| -> Result<CommitBytes, MlsError> {
+            let key_package = validate_key_package(kp_bytes, backend.crypto())?;
+            policy::check_key_package(&key_package)?;
 
-            let key_package = key_package_in.validate(backend.crypto(), ProtocolVersion::default())
-                .map_err(|e| JsValue::from_str(&format!("Key package validation failed: {:?}", e)))?;
+            let signer = group_signer(&group)?;
+            ensure_ratchet_tree_extension(&mut group, &backend)?;
 
-            let signer = get_or_create_signer(group.ciphersuite())
-                .map_err(|e| JsValue::from_str(&e))?;
+            // add_members commits every queued proposal along with the Add
+            let proposal_refs = pending_proposal_refs(&group)?;
 
             let (commit, welcome, _group_info) = group.add_members(&*backend, &signer, &[key_package])
-                .map_err(|e| JsValue::from_str(&format!("Failed to add member: {:?}", e)))?;
+                .map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to add member", &e))?;
 
-            group.merge_pending_commit(&*backend)
-                .map_err(|e| JsValue::from_str(&format!("Failed to merge commit: {:?}", e)))?;
-
-            let output = CommitOutput {
-                proposals: vec![],
-                commit: hex::encode(commit.tls_serialize_detached()
-                    .map_err(|e| JsValue::from_str(&format!("Commit serialization error: {:?}", e)))?),
-                welcome: Some(hex::encode(welcome.tls_serialize_detached()
-                    .map_err(|e| JsValue::from_str(&format!("Welcome serialization error: {:?}", e)))?)),
-                epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
-            };
+            // Leave the commit pending until the delivery service accepts it (confirm_pending_commit)
+            commit_bytes(&group, proposal_refs, &commit, Some(&welcome))
+        })();
 
-            serde_json::to_string(&output)
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.to_vec(), group);
+
+        result
+    })
+}
+
+/// Configure the acceptance policy add_member applies to key packages for this session.
+/// `policy` is `{ required_extension_capabilities?, required_proposal_capabilities?, required_extensions?,
+/// allowed_credential_types?, require_identity_binding? }` (types are RFC 9420 code points); omitted
+/// fields impose nothing and null/undefined clears the policy. Expired or not-yet-valid key packages
+/// are always rejected. Violations throw with code `PolicyViolation`.
+#[wasm_bindgen]
+pub fn set_key_package_policy(policy: JsValue) -> Result<(), MlsError> {
+    let policy = if policy.is_undefined() || policy.is_null() {
+        policy::KeyPackagePolicy::default()
+    } else {
+        serde_wasm_bindgen::from_value(policy)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid key package policy", &e))?
+    };
+    policy::set_policy(policy);
+    Ok(())
+}
+
+/// Validate a foreign TLS-serialized KeyPackage (signatures, version, keys) without touching
+/// any group, and describe it: identity, signature key, ciphersuite, lifetime window,
+/// capabilities, extensions and hash_ref. Lets the UI show who is joining before an invite is confirmed.
+#[wasm_bindgen]
+pub fn inspect_key_package(key_package: &[u8]) -> Result<String, MlsError> {
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let key_package = validate_key_package(key_package, backend.crypto())?;
+        let info = describe_key_package(&key_package, backend.crypto())?;
+
+        serde_json::to_string(&info)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    })
+}
+
+/// Deserialize a TLS-serialized KeyPackage and verify it
+fn validate_key_package(kp_bytes: &[u8], crypto: &impl OpenMlsCrypto) -> Result<KeyPackage, MlsError> {
+    let key_package_in = KeyPackageIn::tls_deserialize(&mut &*kp_bytes)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid key package", &e))?;
+
+    // The lifetime is checked after the signatures, so on InvalidLifetime it can be trusted
+    let lifetime = unverified_lifetime(&key_package_in);
+
+    let key_package = key_package_in.validate(crypto, ProtocolVersion::default())
+        .map_err(|e| match (e, lifetime) {
+            (KeyPackageVerifyError::InvalidLifetime, Some(lifetime)) => policy::lifetime_violation(&lifetime),
+            (e, _) => MlsError::wrap(ErrorCode::ValidationFailed, "Key package validation failed", &e),
+        })?;
+
+    // OpenMLS 0.7 only checks that the lifetime covers the current time, not its total range
+    if !key_package.life_time().has_acceptable_range() {
+        return Err(policy::lifetime_violation(key_package.life_time()));
+    }
+    Ok(key_package)
+}
+
+/// Lifetime of a not yet validated key package.
+/// KeyPackageIn has no accessors, but shares its serde representation with KeyPackage.
+fn unverified_lifetime(key_package_in: &KeyPackageIn) -> Option<Lifetime> {
+    let value = serde_json::to_value(key_package_in).ok()?;
+    let key_package: KeyPackage = serde_json::from_value(value).ok()?;
+    Some(*key_package.life_time())
+}
+
+/// Remove every leaf belonging to a credential identity from the group. Queued proposals are
+/// committed too and listed in `proposals`.
+/// The commit is left pending until confirm_pending_commit (or discard_pending_commit) is called.
+#[wasm_bindgen]
+pub fn remove_member(group_id_hex: &str, credential_identity: &[u8]) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<String, MlsError> {
+            // A user may occupy several leaves (one per device), so remove all of them.
+            // Our own leaf is skipped: removing ourselves requires another member's commit.
+            let own_index = group.own_leaf_index();
+            let leaf_indices: Vec<LeafNodeIndex> = group.members()
+                .filter(|m| m.credential.serialized_content() == credential_identity)
+                .map(|m| m.index)
+                .filter(|index| *index != own_index)
+                .collect();
+
+            if leaf_indices.is_empty() {
+                return Err(MlsError::new(ErrorCode::NotFound, "Member not found in group"));
+            }
+
+            let signer = group_signer(&group)?;
+            ensure_ratchet_tree_extension(&mut group, &backend)?;
+
+            // remove_members commits every queued proposal along with the Removes
+            let proposal_refs = pending_proposal_refs(&group)?;
+
+            let (commit, welcome, _group_info) = group.remove_members(&*backend, &signer, &leaf_indices)
+                .map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to remove member", &e))?;
+
+            // Leave the commit pending until the delivery service accepts it (confirm_pending_commit)
+            commit_output(&group, proposal_refs, &commit, welcome.as_ref())
         })();
 
         // Always restore group to WASM storage, even on error
@@ -197,122 +759,126 @@ pub fn add_member(group_id_hex: &str, key_package_hex: &str) -> Result<String, J
 
 /// Process a welcome message to join a group
 #[wasm_bindgen]
-pub fn process_welcome(welcome_hex: &str) -> Result<String, JsValue> {
+pub fn process_welcome(welcome_hex: &str) -> Result<String, MlsError> {
+    let welcome_bytes = hex::decode(welcome_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid welcome hex", &e))?;
+
+    join_from_welcome(&welcome_bytes)
+}
+
+/// Binary variant of process_welcome taking the TLS-serialized Welcome MlsMessage
+#[wasm_bindgen]
+pub fn process_welcome_bytes(welcome: &[u8]) -> Result<String, MlsError> {
+    join_from_welcome(welcome)
+}
+
+fn join_from_welcome(welcome_bytes: &[u8]) -> Result<String, MlsError> {
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let welcome_bytes = hex::decode(welcome_hex)
-            .map_err(|e| JsValue::from_str(&format!("Invalid welcome hex: {:?}", e)))?;
-        let welcome_msg = MlsMessageIn::tls_deserialize(&mut welcome_bytes.as_slice())
-            .map_err(|e| JsValue::from_str(&format!("Invalid welcome message: {:?}", e)))?;
+        let welcome_msg = MlsMessageIn::tls_deserialize(&mut &*welcome_bytes)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid welcome message", &e))?;
 
         let welcome = match welcome_msg.extract() {
             MlsMessageBodyIn::Welcome(w) => w,
-            _ => return Err(JsValue::from_str("Not a welcome message")),
+            _ => return Err(MlsError::new(ErrorCode::InvalidInput, "Not a welcome message")),
         };
 
-        let join_config = MlsGroupJoinConfig::default();
         let staged_welcome = StagedWelcome::new_from_welcome(
             &*backend,
-            &join_config,
+            &ratchet_tree_join_config(),
             welcome,
             None,
-        ).map_err(|e| JsValue::from_str(&format!("Failed to stage welcome: {:?}", e)))?;
+        ).map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to stage welcome", &e))?;
 
         let group = staged_welcome.into_group(&*backend)
-            .map_err(|e| JsValue::from_str(&format!("Failed to join group: {:?}", e)))?;
+            .map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to join group", &e))?;
 
         let group_id = group.group_id().as_slice().to_vec();
 
-        let state = MlsGroupState {
-            group_id: hex::encode(&group_id),
-            epoch: group.epoch().as_u64(),
-            tree_hash: hex::encode(&group_id),
-            epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
-        };
+        let state = MlsGroupState::json(&group);
 
         store_group(group_id, group);
 
-        serde_json::to_string(&state)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+        state
     })
 }
 
-/// Apply a commit to advance the group epoch
+/// Apply a commit to advance the group epoch. A standalone proposal is queued instead, which
+/// blocks encrypt until it is committed or cleared (see encrypt).
 #[wasm_bindgen]
-pub fn apply_commit(group_id_hex: &str, commit_hex: &str) -> Result<String, JsValue> {
+pub fn apply_commit(group_id_hex: &str, commit_hex: &str) -> Result<String, MlsError> {
     let group_id = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let commit_bytes = hex::decode(commit_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid commit hex", &e))?;
+
+    apply_commit_message(&group_id, &commit_bytes)
+}
+
+/// Binary variant of apply_commit taking the raw group ID and TLS-serialized commit MlsMessage
+#[wasm_bindgen]
+pub fn apply_commit_bytes(group_id: &[u8], commit: &[u8]) -> Result<String, MlsError> {
+    apply_commit_message(group_id, commit)
+}
 
+fn apply_commit_message(group_id: &[u8], commit_bytes: &[u8]) -> Result<String, MlsError> {
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let mut group = take_group(&group_id)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        let result = (|| -> Result<String, JsValue> {
-            let commit_bytes = hex::decode(commit_hex)
-                .map_err(|e| JsValue::from_str(&format!("Invalid commit hex: {:?}", e)))?;
-            let message = MlsMessageIn::tls_deserialize(&mut commit_bytes.as_slice())
-                .map_err(|e| JsValue::from_str(&format!("Invalid commit message: {:?}", e)))?;
+        let mut group = take_group(group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-            let protocol_message = match message.extract() {
-                MlsMessageBodyIn::PublicMessage(pm) => ProtocolMessage::from(pm),
-                MlsMessageBodyIn::PrivateMessage(pm) => ProtocolMessage::from(pm),
-                _ => return Err(JsValue::from_str("Unexpected message type")),
-            };
+        let result = (|| -> Result<String, MlsError> {
+            let protocol_message = parse_protocol_message(commit_bytes)?;
 
             let processed = group.process_message(&*backend, protocol_message)
-                .map_err(|e| JsValue::from_str(&format!("Failed to process commit: {:?}", e)))?;
+                .map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to process commit", &e))?;
 
             match processed.into_content() {
                 ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
-                    group.merge_staged_commit(&*backend, *staged_commit)
-                        .map_err(|e| JsValue::from_str(&format!("Failed to merge commit: {:?}", e)))?;
+                    merge_remote_commit(&mut group, &backend, *staged_commit)?;
+                },
+                // Queue standalone proposals so a later commit referencing them can be applied
+                ProcessedMessageContent::ProposalMessage(proposal)
+                | ProcessedMessageContent::ExternalJoinProposalMessage(proposal) => {
+                    group.store_pending_proposal(backend.storage(), *proposal)
+                        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to store proposal", &e))?;
                 },
-                _ => return Err(JsValue::from_str("Expected a commit message")),
+                _ => return Err(MlsError::new(ErrorCode::InvalidInput, "Expected a commit message")),
             }
 
-            let state = MlsGroupState {
-                group_id: hex::encode(&group_id),
-                epoch: group.epoch().as_u64(),
-                tree_hash: hex::encode(&group_id),
-                epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
-            };
-
-            serde_json::to_string(&state)
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+            MlsGroupState::json(&group)
         })();
 
         // Always restore group to WASM storage, even on error
-        store_group(group_id.clone(), group);
+        store_group(group_id.to_vec(), group);
 
         result
     })
 }
 
-/// Encrypt a message for the group
+/// Merge our pending commit once the delivery service has accepted it, advancing the epoch.
 #[wasm_bindgen]
-pub fn encrypt(group_id_hex: &str, plaintext: &str) -> Result<String, JsValue> {
+pub fn confirm_pending_commit(group_id_hex: &str) -> Result<String, MlsError> {
     let group_id = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
 
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let mut group = take_group(&group_id)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-        let result = (|| -> Result<String, JsValue> {
-            let signer = get_or_create_signer(group.ciphersuite())
-                .map_err(|e| JsValue::from_str(&e))?;
+        let result = (|| -> Result<String, MlsError> {
+            if group.pending_commit().is_none() {
+                return Err(MlsError::new(ErrorCode::InvalidState, "No pending commit"));
+            }
 
-            let message = group.create_message(&*backend, &signer, plaintext.as_bytes())
-                .map_err(|e| JsValue::from_str(&format!("Encryption failed: {:?}", e)))?;
+            group.merge_pending_commit(&*backend)
+                .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to merge commit", &e))?;
 
-            let ciphertext = message.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))?;
-            Ok(hex::encode(ciphertext))
+            MlsGroupState::json(&group)
         })();
 
         // Always restore group to WASM storage, even on error
@@ -322,41 +888,23 @@ pub fn encrypt(group_id_hex: &str, plaintext: &str) -> Result<String, JsValue> {
     })
 }
 
-/// Decrypt a message from the group
+/// Throw away our pending commit (e.g. the delivery service rejected it), staying in the current epoch.
 #[wasm_bindgen]
-pub fn decrypt(group_id_hex: &str, ciphertext_hex: &str) -> Result<String, JsValue> {
+pub fn discard_pending_commit(group_id_hex: &str) -> Result<String, MlsError> {
     let group_id = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
 
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let mut group = take_group(&group_id)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
-
-        let result = (|| -> Result<String, JsValue> {
-            let ct_bytes = hex::decode(ciphertext_hex)
-                .map_err(|e| JsValue::from_str(&format!("Invalid ciphertext hex: {:?}", e)))?;
-            let message = MlsMessageIn::tls_deserialize(&mut ct_bytes.as_slice())
-                .map_err(|e| JsValue::from_str(&format!("Invalid message: {:?}", e)))?;
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-            let protocol_message = match message.extract() {
-                MlsMessageBodyIn::PublicMessage(pm) => ProtocolMessage::from(pm),
-                MlsMessageBodyIn::PrivateMessage(pm) => ProtocolMessage::from(pm),
-                _ => return Err(JsValue::from_str("Unexpected message type")),
-            };
-
-            let processed = group.process_message(&*backend, protocol_message)
-                .map_err(|e| JsValue::from_str(&format!("Decryption failed: {:?}", e)))?;
+        let result = (|| -> Result<String, MlsError> {
+            group.clear_pending_commit(backend.storage())
+                .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to discard commit", &e))?;
 
-            match processed.into_content() {
-                ProcessedMessageContent::ApplicationMessage(app_msg) => {
-                    let plaintext = String::from_utf8(app_msg.into_bytes())
-                        .map_err(|_| JsValue::from_str("Invalid UTF-8 in plaintext"))?;
-                    Ok(plaintext)
-                },
-                _ => Err(JsValue::from_str("Not an application message")),
-            }
+            MlsGroupState::json(&group)
         })();
 
         // Always restore group to WASM storage, even on error
@@ -366,129 +914,1544 @@ pub fn decrypt(group_id_hex: &str, ciphertext_hex: &str) -> Result<String, JsVal
     })
 }
 
-/// Create an update proposal for forward secrecy
+/// Merge a commit received from another member. If we have a pending commit of our own,
+/// the incoming one won the ordering race for this epoch, so ours is rolled back first.
+/// Returns whether a pending commit was discarded.
+fn merge_remote_commit(
+    group: &mut MlsGroup,
+    backend: &MlsProvider,
+    staged_commit: StagedCommit,
+) -> Result<bool, MlsError> {
+    let discarded = group.pending_commit().is_some();
+    if discarded {
+        group.clear_pending_commit(backend.storage())
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to discard pending commit", &e))?;
+    }
+
+    group.merge_staged_commit(backend, staged_commit)
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to merge commit", &e))?;
+
+    Ok(discarded)
+}
+
+/// Encrypt a message for the group.
+/// Fails with InvalidState (variant "GroupStateError::PendingProposal") while proposals are
+/// queued: commit them (commit_pending_proposals) or drop them (clear_pending_proposals) first.
 #[wasm_bindgen]
-pub fn create_update_proposal(group_id_hex: &str) -> Result<String, JsValue> {
+pub fn encrypt(group_id_hex: &str, plaintext: &str) -> Result<String, MlsError> {
     let group_id = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    encrypt_message(&group_id, plaintext.as_bytes()).map(hex::encode)
+}
+
+/// Binary variant of encrypt: raw group ID and plaintext in, TLS-serialized MlsMessage out
+#[wasm_bindgen]
+pub fn encrypt_bytes(group_id: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, MlsError> {
+    encrypt_message(group_id, plaintext)
+}
 
+fn encrypt_message(group_id: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, MlsError> {
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let mut group = take_group(&group_id)
-            .ok_or_else(|| JsValue::from_str("Group not found"))?;
+        let mut group = take_group(group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-        let result = (|| -> Result<String, JsValue> {
-            let signer = get_or_create_signer(group.ciphersuite())
-                .map_err(|e| JsValue::from_str(&e))?;
+        let result = (|| -> Result<Vec<u8>, MlsError> {
+            let signer = group_signer(&group)?;
 
-            let leaf_node_params = LeafNodeParameters::default();
-            let (proposal, _proposal_ref) = group.propose_self_update(&*backend, &signer, leaf_node_params)
-                .map_err(|e| JsValue::from_str(&format!("Failed to create update proposal: {:?}", e)))?;
+            let message = group.create_message(&*backend, &signer, plaintext)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidState, "Encryption failed", &e))?;
 
-            let proposal_bytes = proposal.tls_serialize_detached()
-                .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))?;
-            Ok(hex::encode(proposal_bytes))
+            message.tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
         })();
 
         // Always restore group to WASM storage, even on error
-        store_group(group_id.clone(), group);
+        store_group(group_id.to_vec(), group);
 
         result
     })
 }
 
-/// Export the full WASM state (backend storage + signer) as a JSON string.
-/// Call this after important operations (create_group, process_welcome, add_member)
-/// and save the result to persistent storage (IndexedDB) to enable cross-session restore.
+/// Decrypt a message from the group.
+/// A proposal is not an error for the group: it is queued for the next commit (see
+/// list_pending_proposals), but decrypt has no plaintext to return and throws InvalidInput with
+/// variant "ProposalQueued". Callers should treat that as handled; process_message reports
+/// every message kind without throwing. Until the queue is committed or cleared, encrypt fails
+/// with PendingProposal.
+#[wasm_bindgen]
+pub fn decrypt(group_id_hex: &str, ciphertext_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let ct_bytes = hex::decode(ciphertext_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid ciphertext hex", &e))?;
+
+    let plaintext = decrypt_message(&group_id, &ct_bytes)?;
+    String::from_utf8(plaintext)
+        .map_err(|_| MlsError::new(ErrorCode::InvalidInput, "Invalid UTF-8 in plaintext"))
+}
+
+/// Binary variant of decrypt: raw group ID and TLS-serialized MlsMessage in, plaintext bytes out
 #[wasm_bindgen]
-pub fn export_state() -> Result<String, JsValue> {
-    // Serialize all key-value pairs from the shared backend's MemoryStorage
-    let storage_hex_map: HashMap<String, String> = BACKEND.with(|b| -> Result<HashMap<String, String>, JsValue> {
+pub fn decrypt_bytes(group_id: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, MlsError> {
+    decrypt_message(group_id, ciphertext)
+}
+
+fn decrypt_message(group_id: &[u8], ct_bytes: &[u8]) -> Result<Vec<u8>, MlsError> {
+    BACKEND.with(|b| {
         let backend = b.borrow();
-        let storage = backend.storage();
-        let values = storage.values.read()
-            .map_err(|_| JsValue::from_str("Storage lock poisoned"))?;
-        Ok(values.iter()
-            .map(|(k, v)| (hex::encode(k), hex::encode(v)))
-            .collect())
-    })?;
 
-    let signer_json = storage::get_signer_json();
+        let mut group = take_group(group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-    let state = serde_json::json!({
-        "storage": storage_hex_map,
-        "signer": signer_json,
-    });
+        let result = (|| -> Result<Vec<u8>, MlsError> {
+            let protocol_message = parse_protocol_message(ct_bytes)?;
 
-    serde_json::to_string(&state)
-        .map_err(|e| JsValue::from_str(&format!("State serialization failed: {}", e)))
-}
+            let processed = group.process_message(&*backend, protocol_message)
+                .map_err(|e| MlsError::wrap(ErrorCode::DecryptionFailed, "Decryption failed", &e))?;
 
-/// Import previously exported WASM state.
-/// Call this on app start before calling load_group to restore groups from storage.
-#[wasm_bindgen]
-pub fn import_state(state_json: &str) -> Result<(), JsValue> {
-    #[derive(Deserialize)]
-    struct WasmState {
-        storage: HashMap<String, String>,
-        signer: Option<String>,
-    }
+            match processed.into_content() {
+                ProcessedMessageContent::ApplicationMessage(app_msg) => Ok(app_msg.into_bytes()),
+                ProcessedMessageContent::ProposalMessage(proposal)
+                | ProcessedMessageContent::ExternalJoinProposalMessage(proposal) => {
+                    group.store_pending_proposal(backend.storage(), *proposal)
+                        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to store proposal", &e))?;
+                    Err(MlsError::with_variant(
+                        ErrorCode::InvalidInput,
+                        "ProposalQueued",
+                        "Not an application message: the proposal was queued for the next commit",
+                    ))
+                },
+                _ => Err(MlsError::new(ErrorCode::InvalidInput, "Not an application message")),
+            }
+        })();
 
-    let state: WasmState = serde_json::from_str(state_json)
-        .map_err(|e| JsValue::from_str(&format!("Invalid state JSON: {}", e)))?;
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.to_vec(), group);
 
-    // Restore backend storage values
-    BACKEND.with(|b| -> Result<(), JsValue> {
-        let backend = b.borrow();
-        let storage = backend.storage();
-        let mut values = storage.values.write()
-            .map_err(|_| JsValue::from_str("Storage lock poisoned"))?;
-        values.clear();
-        for (k_hex, v_hex) in &state.storage {
-            let k = hex::decode(k_hex)
-                .map_err(|e| JsValue::from_str(&format!("Invalid storage key hex: {}", e)))?;
-            let v = hex::decode(v_hex)
-                .map_err(|e| JsValue::from_str(&format!("Invalid storage value hex: {}", e)))?;
-            values.insert(k, v);
-        }
-        Ok(())
-    })?;
+        result
+    })
+}
 
-    // Restore signer
-    if let Some(signer_json) = state.signer {
-        storage::set_signer_json(signer_json);
-    }
+/// Sender of a processed message, as reported by process_message
+#[derive(Serialize)]
+struct MessageSender {
+    /// "member", "external", "new_member_proposal" or "new_member_commit"
+    kind: &'static str,
+    leaf_index: Option<u32>,
+    /// Hex-encoded credential identity (null if it cannot be resolved)
+    identity: Option<String>,
+}
 
-    Ok(())
+/// Kind-specific part of a process_message result, tagged by `type`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ProcessedContent {
+    Application {
+        /// UTF-8 plaintext, or null if the payload is not valid UTF-8
+        plaintext: Option<String>,
+        data: String,
+    },
+    Commit {
+        epoch_authenticator: String,
+        self_removed: bool,
+        proposal_types: Vec<&'static str>,
+        /// Our own pending commit for the same epoch lost the race and was rolled back
+        discarded_pending_commit: bool,
+    },
+    Proposal {
+        proposal_ref: String,
+        proposal_type: &'static str,
+    },
+    ExternalJoin {
+        proposal_ref: String,
+        proposal_type: &'static str,
+    },
 }
 
-/// Load a previously persisted MLS group from the shared backend's storage.
-/// Call this after import_state to restore groups into the in-memory GROUPS map.
-/// group_id_hex is the MLS group ID (hex-encoded), as returned by create_group/process_welcome.
+#[derive(Serialize)]
+struct ProcessedMessageOutput {
+    group_id: String,
+    sender: MessageSender,
+    /// Epoch the message was sent in
+    message_epoch: u64,
+    /// Group epoch after processing (advanced for merged commits)
+    epoch: u64,
+    #[serde(flatten)]
+    content: ProcessedContent,
+}
+
+/// Process any handshake or application message received from the delivery service.
+/// Application messages are decrypted, commits are staged and merged, and standalone or
+/// external-join proposals are stored in the group's proposal queue for a later commit
+/// (encrypt fails with PendingProposal until then, see encrypt).
+/// Returns a JSON object tagged by `type`: "application", "commit", "proposal" or "external_join".
 #[wasm_bindgen]
-pub fn load_group(group_id_hex: &str) -> Result<String, JsValue> {
-    let group_id_bytes = hex::decode(group_id_hex)
-        .map_err(|e| JsValue::from_str(&format!("Invalid group ID hex: {:?}", e)))?;
-    let group_id = GroupId::from_slice(&group_id_bytes);
+pub fn process_message(group_id_hex: &str, message_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
 
     BACKEND.with(|b| {
         let backend = b.borrow();
 
-        let group = MlsGroup::load(backend.storage(), &group_id)
-            .map_err(|e| JsValue::from_str(&format!("Failed to load group from storage: {:?}", e)))?
-            .ok_or_else(|| JsValue::from_str("Group not found in storage"))?;
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
 
-        let state = MlsGroupState {
-            group_id: hex::encode(&group_id_bytes),
-            epoch: group.epoch().as_u64(),
-            tree_hash: hex::encode(&group_id_bytes),
-            epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
-        };
+        let result = (|| -> Result<String, MlsError> {
+            let message_bytes = hex::decode(message_hex)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid message hex", &e))?;
+            let protocol_message = parse_protocol_message(&message_bytes)?;
+
+            let processed = group.process_message(&*backend, protocol_message)
+                .map_err(|e| MlsError::wrap(ErrorCode::ValidationFailed, "Failed to process message", &e))?;
+
+            let sender = message_sender(processed.sender(), Some(processed.credential()));
+            let message_epoch = processed.epoch().as_u64();
+
+            let content = match processed.into_content() {
+                ProcessedMessageContent::ApplicationMessage(app_msg) => {
+                    let bytes = app_msg.into_bytes();
+                    ProcessedContent::Application {
+                        plaintext: String::from_utf8(bytes.clone()).ok(),
+                        data: hex::encode(bytes),
+                    }
+                },
+                ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                    let self_removed = staged_commit.self_removed();
+                    let proposal_types = staged_commit.queued_proposals()
+                        .map(|p| proposal_type_name(p.proposal()))
+                        .collect();
+                    let discarded_pending_commit = merge_remote_commit(&mut group, &backend, *staged_commit)?;
+                    ProcessedContent::Commit {
+                        epoch_authenticator: hex::encode(group.epoch_authenticator().as_slice()),
+                        self_removed,
+                        proposal_types,
+                        discarded_pending_commit,
+                    }
+                },
+                ProcessedMessageContent::ProposalMessage(proposal) => {
+                    let proposal_ref = hex::encode(queued_proposal_ref(&proposal)?.as_slice());
+                    let proposal_type = proposal_type_name(proposal.proposal());
+                    group.store_pending_proposal(backend.storage(), *proposal)
+                        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to store proposal", &e))?;
+                    ProcessedContent::Proposal { proposal_ref, proposal_type }
+                },
+                ProcessedMessageContent::ExternalJoinProposalMessage(proposal) => {
+                    let proposal_ref = hex::encode(queued_proposal_ref(&proposal)?.as_slice());
+                    let proposal_type = proposal_type_name(proposal.proposal());
+                    group.store_pending_proposal(backend.storage(), *proposal)
+                        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to store proposal", &e))?;
+                    ProcessedContent::ExternalJoin { proposal_ref, proposal_type }
+                },
+            };
+
+            let output = ProcessedMessageOutput {
+                group_id: hex::encode(&group_id),
+                sender,
+                message_epoch,
+                epoch: group.epoch().as_u64(),
+                content,
+            };
+
+            serde_json::to_string(&output)
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// A proposal waiting in the group's proposal queue, as reported by list_pending_proposals
+#[derive(Serialize)]
+struct PendingProposal {
+    proposal_ref: String,
+    proposal_type: &'static str,
+    sender: MessageSender,
+    /// Hex identity of the member being added, removed or updated, if any
+    subject_identity: Option<String>,
+    /// Leaf index targeted by a remove proposal
+    removed_leaf_index: Option<u32>,
+    /// Extension types set by a group context extensions proposal
+    extension_types: Option<Vec<u16>>,
+}
+
+/// List the proposals queued in the group's proposal store (received or our own),
+/// which the next commit will include.
+#[wasm_bindgen]
+pub fn list_pending_proposals(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    let group = take_group(&group_id)?
+        .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+    let result = (|| -> Result<String, MlsError> {
+        let proposals = group.pending_proposals()
+            .map(|queued| describe_proposal(&group, queued))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        serde_json::to_string(&proposals)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    })();
+
+    // Always restore group to WASM storage, even on error
+    store_group(group_id.clone(), group);
+
+    result
+}
+
+/// Drop a single queued proposal (by hex proposal ref) so the next commit does not include it
+#[wasm_bindgen]
+pub fn discard_pending_proposal(group_id_hex: &str, proposal_ref_hex: &str) -> Result<(), MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let proposal_ref_bytes = hex::decode(proposal_ref_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid proposal ref hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<(), MlsError> {
+            let mut proposal_ref = None;
+            for queued in group.pending_proposals() {
+                let r = queued_proposal_ref(queued)?;
+                if r.as_slice() == proposal_ref_bytes.as_slice() {
+                    proposal_ref = Some(r);
+                    break;
+                }
+            }
+            let proposal_ref = proposal_ref
+                .ok_or_else(|| MlsError::new(ErrorCode::NotFound, "Proposal not found"))?;
+
+            group.remove_pending_proposal(backend.storage(), &proposal_ref)
+                .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to discard proposal", &e))
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// Drop every queued proposal of the group
+#[wasm_bindgen]
+pub fn clear_pending_proposals(group_id_hex: &str) -> Result<(), MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = group.clear_pending_proposals(backend.storage())
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to clear proposals", &e));
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+fn describe_proposal(group: &MlsGroup, queued: &QueuedProposal) -> Result<PendingProposal, MlsError> {
+    let proposal = queued.proposal();
+    let identity_at = |index: LeafNodeIndex| group.member(index)
+        .map(|c| hex::encode(c.serialized_content()));
+
+    let sender_credential = match queued.sender() {
+        Sender::Member(index) => group.member(*index),
+        // An external join carries its own credential in the key package
+        Sender::NewMemberProposal => match proposal {
+            Proposal::Add(add) => Some(add.key_package().leaf_node().credential()),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let mut described = PendingProposal {
+        proposal_ref: hex::encode(queued_proposal_ref(queued)?.as_slice()),
+        proposal_type: proposal_type_name(proposal),
+        sender: message_sender(queued.sender(), sender_credential),
+        subject_identity: None,
+        removed_leaf_index: None,
+        extension_types: None,
+    };
+
+    match proposal {
+        Proposal::Add(add) => {
+            described.subject_identity = Some(hex::encode(
+                add.key_package().leaf_node().credential().serialized_content(),
+            ));
+        },
+        Proposal::Update(update) => {
+            described.subject_identity = Some(hex::encode(
+                update.leaf_node().credential().serialized_content(),
+            ));
+        },
+        Proposal::Remove(remove) => {
+            described.removed_leaf_index = Some(remove.removed().u32());
+            described.subject_identity = identity_at(remove.removed());
+        },
+        Proposal::SelfRemove => {
+            if let Sender::Member(index) = queued.sender() {
+                described.removed_leaf_index = Some(index.u32());
+                described.subject_identity = identity_at(*index);
+            }
+        },
+        Proposal::GroupContextExtensions(gce) => {
+            described.extension_types = Some(gce.extensions().iter()
+                .map(|ext| u16::from(ext.extension_type()))
+                .collect());
+        },
+        _ => {},
+    }
+
+    Ok(described)
+}
+
+/// Deserialize an MlsMessage and unwrap the Public/PrivateMessage it carries
+fn parse_protocol_message(bytes: &[u8]) -> Result<ProtocolMessage, MlsError> {
+    let message = MlsMessageIn::tls_deserialize(&mut &*bytes)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid message", &e))?;
+
+    match message.extract() {
+        MlsMessageBodyIn::PublicMessage(pm) => Ok(ProtocolMessage::from(pm)),
+        MlsMessageBodyIn::PrivateMessage(pm) => Ok(ProtocolMessage::from(pm)),
+        _ => Err(MlsError::new(ErrorCode::InvalidInput, "Unexpected message type")),
+    }
+}
+
+fn message_sender(sender: &Sender, credential: Option<&Credential>) -> MessageSender {
+    let (kind, leaf_index) = match sender {
+        Sender::Member(index) => ("member", Some(index.u32())),
+        Sender::External(_) => ("external", None),
+        Sender::NewMemberProposal => ("new_member_proposal", None),
+        Sender::NewMemberCommit => ("new_member_commit", None),
+    };
+    MessageSender {
+        kind,
+        leaf_index,
+        identity: credential.map(|c| hex::encode(c.serialized_content())),
+    }
+}
+
+/// Stable name for a proposal type, as exposed to JS
+fn proposal_type_name(proposal: &Proposal) -> &'static str {
+    match proposal {
+        Proposal::Add(_) => "add",
+        Proposal::Update(_) => "update",
+        Proposal::Remove(_) => "remove",
+        Proposal::PreSharedKey(_) => "psk",
+        Proposal::ReInit(_) => "reinit",
+        Proposal::ExternalInit(_) => "external_init",
+        Proposal::GroupContextExtensions(_) => "group_context_extensions",
+        Proposal::AppAck(_) => "app_ack",
+        Proposal::SelfRemove => "self_remove",
+        Proposal::Custom(_) => "custom",
+    }
+}
+
+/// Create an update proposal for forward secrecy
+#[wasm_bindgen]
+pub fn create_update_proposal(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<String, MlsError> {
+            let signer = group_signer(&group)?;
+
+            let leaf_node_params = LeafNodeParameters::default();
+            let (proposal, _proposal_ref) = group.propose_self_update(&*backend, &signer, leaf_node_params)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidState, "Failed to create update proposal", &e))?;
+
+            let proposal_bytes = proposal.tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))?;
+            Ok(hex::encode(proposal_bytes))
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// Leave a group: create a proposal removing our own leaf, to be committed by another member.
+/// Returns the proposal message hex for the delivery service. We stay a member (and can still
+/// decrypt) until that commit arrives; process it, then call delete_group.
+#[wasm_bindgen]
+pub fn leave_group(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<String, MlsError> {
+            let signer = group_signer(&group)?;
+
+            let proposal = group.leave_group(&*backend, &signer)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidState, "Failed to create remove proposal", &e))?;
+
+            let proposal_bytes = proposal.tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))?;
+            Ok(hex::encode(proposal_bytes))
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// Delete a group from this device: drop it from memory and remove all of its records from
+/// backend storage (tree, epoch and message secrets, key pairs, queued proposals), zeroizing
+/// the stored bytes. OpenMLS secrets zeroize themselves when the dropped group is freed.
+/// This does not notify other members; use leave_group first to leave the conversation.
+#[wasm_bindgen]
+pub fn delete_group(group_id_hex: &str) -> Result<(), MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+        let storage = backend.storage();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        // Entries OpenMLS does not delete itself: our leaf encryption key pair and epoch key
+        // pairs of earlier epochs
+        let leftovers = stored_group_keys(storage, &group)?;
+
+        group.delete(storage)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to delete group from storage", &e))?;
+        for key in &leftovers {
+            storage::erase_storage_value(storage, key)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Commit a path update for our own leaf, rotating our HPKE leaf key (forward secrecy / PCS).
+/// Any pending proposals are included in the commit, which is left pending until confirmed.
+#[wasm_bindgen]
+pub fn self_update_commit(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<String, MlsError> {
+            let signer = group_signer(&group)?;
+            ensure_ratchet_tree_extension(&mut group, &backend)?;
+
+            let proposal_refs = pending_proposal_refs(&group)?;
+
+            let bundle = group.self_update(&*backend, &signer, LeafNodeParameters::default())
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidState, "Failed to create self-update commit", &e))?;
+            let (commit, welcome, _group_info) = bundle.into_messages();
+
+            // Leave the commit pending until the delivery service accepts it (confirm_pending_commit)
+            commit_output(&group, proposal_refs, &commit, welcome.as_ref())
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// Commit all proposals currently queued in the group's proposal store
+/// (e.g. our own update proposal from create_update_proposal). The commit is left pending until confirmed.
+#[wasm_bindgen]
+pub fn commit_pending_proposals(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let mut group = take_group(&group_id)?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+        let result = (|| -> Result<String, MlsError> {
+            let signer = group_signer(&group)?;
+            ensure_ratchet_tree_extension(&mut group, &backend)?;
+
+            let proposal_refs = pending_proposal_refs(&group)?;
+
+            let (commit, welcome, _group_info) = group.commit_to_pending_proposals(&*backend, &signer)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidState, "Failed to commit pending proposals", &e))?;
+
+            // Leave the commit pending until the delivery service accepts it (confirm_pending_commit)
+            commit_output(&group, proposal_refs, &commit, welcome.as_ref())
+        })();
+
+        // Always restore group to WASM storage, even on error
+        store_group(group_id.clone(), group);
+
+        result
+    })
+}
+
+/// Hex-encoded refs of the proposals currently queued in the group's proposal store
+fn pending_proposal_refs(group: &MlsGroup) -> Result<Vec<String>, MlsError> {
+    group.pending_proposals()
+        .map(|p| queued_proposal_ref(p).map(|r| hex::encode(r.as_slice())))
+        .collect()
+}
+
+/// Join config for groups joined from a Welcome. Like groups from create_group, they put the
+/// ratchet tree in the Welcomes they send, since process_welcome is given no tree.
+fn ratchet_tree_join_config() -> MlsGroupJoinConfig {
+    MlsGroupJoinConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build()
+}
+
+/// Turn the ratchet tree extension on for a group created or joined before it was enabled, so
+/// Welcomes from its next commit can be joined. MlsGroupJoinConfig has no getter for the flag,
+/// so it is read and set through the config's serde representation.
+fn ensure_ratchet_tree_extension(group: &mut MlsGroup, backend: &MlsProvider) -> Result<(), MlsError> {
+    let mut config = serde_json::to_value(group.configuration())
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Group config serialization error", &e))?;
+    if config["use_ratchet_tree_extension"] == serde_json::Value::Bool(true) {
+        return Ok(());
+    }
+    config["use_ratchet_tree_extension"] = true.into();
+    let config: MlsGroupJoinConfig = serde_json::from_value(config)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Invalid group config", &e))?;
+    group.set_configuration(backend.storage(), &config)
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to update group config", &e))
+}
+
+/// Read the ProposalRef of a queued proposal.
+/// OpenMLS 0.7 keeps the accessor crate-private, but the field is part of the
+/// serde representation used by the storage provider, so we read it from there.
+fn queued_proposal_ref(proposal: &QueuedProposal) -> Result<ProposalRef, MlsError> {
+    let mut value = serde_json::to_value(proposal)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Proposal serialization error", &e))?;
+    serde_json::from_value(value["proposal_reference"].take())
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Invalid proposal reference", &e))
+}
+
+/// Capabilities advertised by a leaf node (of a member or a key package).
+/// Versions, ciphersuites, extensions, proposals and credentials are RFC 9420 code points.
+#[derive(Serialize)]
+struct MemberCapabilities {
+    versions: Vec<u16>,
+    ciphersuites: Vec<u16>,
+    extensions: Vec<u16>,
+    proposals: Vec<u16>,
+    credentials: Vec<u16>,
+}
+
+/// A member of the group, as recorded in its ratchet tree
+#[derive(Serialize)]
+struct GroupMember {
+    leaf_index: u32,
+    /// Hex credential identity
+    identity: String,
+    credential_type: u16,
+    /// Hex signature public key
+    signature_key: String,
+    capabilities: MemberCapabilities,
+    is_self: bool,
+}
+
+/// List the members of the group straight from its ratchet tree, ordered by leaf index.
+/// Unlike the server's member list, this is what the group's cryptographic state agrees on.
+#[wasm_bindgen]
+pub fn list_members(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    let group = take_group(&group_id)?
+        .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+    let result = (|| -> Result<String, MlsError> {
+        let own_index = group.own_leaf_index();
+        let members = ratchet_tree_leaves(&group)?
+            .into_iter()
+            .map(|(leaf_index, leaf)| -> Result<GroupMember, MlsError> {
+                Ok(GroupMember {
+                    leaf_index,
+                    identity: hex::encode(leaf.credential().serialized_content()),
+                    credential_type: leaf.credential().credential_type().into(),
+                    signature_key: hex::encode(leaf.signature_key().as_slice()),
+                    capabilities: member_capabilities(leaf.capabilities())?,
+                    is_self: leaf_index == own_index.u32(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        serde_json::to_string(&members)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+    })();
+
+    // Always restore group to WASM storage, even on error
+    store_group(group_id.clone(), group);
+
+    result
+}
+
+/// Our own leaf index in the group's ratchet tree
+#[wasm_bindgen]
+pub fn own_leaf_index(group_id_hex: &str) -> Result<u32, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    let group = take_group(&group_id)?
+        .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+    let leaf_index = group.own_leaf_index().u32();
+
+    store_group(group_id.clone(), group);
+
+    Ok(leaf_index)
+}
+
+/// Non-blank leaves of the group's ratchet tree with their leaf indices.
+/// OpenMLS 0.7 keeps the tree's nodes crate-private, but the exported RatchetTree
+/// serializes as its node list (leaf i at node index 2i), so we read them from there.
+fn ratchet_tree_leaves(group: &MlsGroup) -> Result<Vec<(u32, LeafNode)>, MlsError> {
+    let tree = serde_json::to_value(group.export_ratchet_tree())
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Ratchet tree serialization error", &e))?;
+    let nodes: Vec<Option<Node>> = serde_json::from_value(tree)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Ratchet tree deserialization error", &e))?;
+
+    Ok(nodes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(node_index, node)| match node {
+            Some(Node::LeafNode(leaf)) => Some(((node_index / 2) as u32, *leaf)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn member_capabilities(capabilities: &Capabilities) -> Result<MemberCapabilities, MlsError> {
+    let ciphersuites = capabilities.ciphersuites()
+        .iter()
+        .map(|cs| {
+            // VerifiableCiphersuite keeps unknown code points, but only exposes them via TLS
+            let bytes = cs.tls_serialize_detached()
+                .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Ciphersuite serialization error", &e))?;
+            Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+        })
+        .collect::<Result<Vec<_>, MlsError>>()?;
+
+    Ok(MemberCapabilities {
+        versions: capabilities.versions()
+            .iter()
+            .map(|v| match v {
+                ProtocolVersion::Mls10 => 1,
+                ProtocolVersion::Other(other) => *other,
+            })
+            .collect(),
+        ciphersuites,
+        extensions: capabilities.extensions().iter().map(|&t| t.into()).collect(),
+        proposals: capabilities.proposals().iter().map(|&t| t.into()).collect(),
+        credentials: capabilities.credentials().iter().map(|&t| t.into()).collect(),
+    })
+}
+
+/// Export the full WASM state (backend storage + signers) as a JSON string.
+/// Call this after important operations (create_group, process_welcome, add_member)
+/// and save the result to persistent storage (IndexedDB) to enable cross-session restore.
+#[wasm_bindgen]
+pub fn export_state() -> Result<String, MlsError> {
+    // Serialize all key-value pairs from the shared backend's storage. This snapshot becomes
+    // the base for later deltas, so tracked changes are reset.
+    let storage_hex_map: HashMap<String, String> = BACKEND.with(|b| -> Result<HashMap<String, String>, MlsError> {
+        let backend = b.borrow();
+        let storage = backend.storage();
+        let values = storage.values.read()
+            .map_err(|_| MlsError::new(ErrorCode::StorageError, "Storage lock poisoned"))?;
+        let map = values.iter()
+            .map(|(k, v)| (hex::encode(k), hex::encode(v)))
+            .collect();
+        storage.clear_changes()
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to reset change tracking", &e))?;
+        Ok(map)
+    })?;
+
+    let state = state::WasmState::new(storage_hex_map, state::SignerEntry::encode(storage::get_signer_jsons()));
+
+    serde_json::to_string(&state)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "State serialization failed", &e))
+}
+
+/// Import previously exported WASM state.
+/// Call this on app start before calling load_group to restore groups from storage.
+/// States from older builds are migrated; a newer format or a different OpenMLS storage
+/// version fails with IncompatibleState before anything is replaced.
+#[wasm_bindgen]
+pub fn import_state(state_json: &str) -> Result<(), MlsError> {
+    let state = state::WasmState::parse(state_json)?;
+
+    // Restore backend storage values
+    let values = decode_storage_entries(&state.storage)?;
+    BACKEND.with(|b| {
+        b.borrow().storage().replace(values)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to restore storage", &e))
+    })?;
+
+    // Restore signers
+    for entry in state.signers {
+        storage::set_signer_json(entry.identity_bytes()?, entry.signer)?;
+    }
+    storage::bind_unbound_signers()?;
+
+    Ok(())
+}
+
+/// Write backend storage through to a JS key/value store so every OpenMLS write is persisted
+/// as it happens, without export_state. `store` must provide synchronous
+/// `keys(): Uint8Array[]`, `get(key): Uint8Array | undefined`, `put(key, value)` and `delete(key)`
+/// (e.g. a Map preloaded from IndexedDB that queues its writes back).
+/// Call once at startup, before anything creates or imports state: the store's entries are
+/// loaded as they are, and attaching fails with InvalidState once backend storage holds entries.
+/// To migrate a legacy snapshot, call import_state afterwards; it is written through.
+#[wasm_bindgen]
+pub fn use_js_storage(store: JsValue) -> Result<(), MlsError> {
+    let store = JsStore::new(store)
+        .ok_or_else(|| MlsError::new(ErrorCode::InvalidInput, "Storage object must provide keys, get, put and delete functions"))?;
+
+    BACKEND.with(|b| {
+        b.borrow().storage().attach(store)
+            .map_err(|e| match e {
+                provider::TrackingStorageError::NotEmpty => MlsError::wrap(
+                    ErrorCode::InvalidState,
+                    "JS storage must be attached once, before any state is created or imported",
+                    &e,
+                ),
+                _ => MlsError::wrap(ErrorCode::StorageError, "Failed to attach JS storage", &e),
+            })
+    })
+}
+
+/// Export only what changed in backend storage since the last export_state, import_state or
+/// export_state_delta, plus the current signers, as JSON:
+/// `{"version", "storage_version", "storage": {hex_key: hex_val, ...}, "deleted": [hex_key, ...], "signers": {...}}`.
+/// Cheap enough to call after every encrypt/decrypt. Deltas must be applied in order on top of
+/// the snapshot they follow; take a full export_state from time to time to compact them.
+#[wasm_bindgen]
+pub fn export_state_delta() -> Result<String, MlsError> {
+    let delta = BACKEND.with(|b| {
+        b.borrow().storage().take_changes()
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to collect storage changes", &e))
+    })?;
+
+    let storage_hex_map: HashMap<String, String> = delta.written.iter()
+        .map(|(k, v)| (hex::encode(k), hex::encode(v)))
+        .collect();
+    let deleted: Vec<String> = delta.deleted.iter().map(hex::encode).collect();
+
+    let state = state::WasmStateDelta::new(storage_hex_map, deleted, state::SignerEntry::encode(storage::get_signer_jsons()));
+
+    serde_json::to_string(&state)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "State serialization failed", &e))
+}
+
+/// Apply a delta from export_state_delta on top of the current state (after import_state and
+/// any earlier deltas, in the order they were exported). Call before load_group.
+#[wasm_bindgen]
+pub fn apply_state_delta(delta_json: &str) -> Result<(), MlsError> {
+    let delta = state::WasmStateDelta::parse(delta_json)?;
+
+    let written = decode_storage_entries(&delta.storage)?;
+    let deleted = delta.deleted.iter()
+        .map(|k_hex| hex::decode(k_hex)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid storage key hex", &e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    BACKEND.with(|b| {
+        b.borrow().storage().apply(StorageDelta { written, deleted })
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to apply state delta", &e))
+    })?;
+
+    for entry in delta.signers {
+        storage::set_signer_json(entry.identity_bytes()?, entry.signer)?;
+    }
+    storage::bind_unbound_signers()?;
+
+    Ok(())
+}
+
+/// A group's entries in backend storage, including its own leaf encryption key pair.
+/// The values are zeroized on drop.
+fn stored_group_entries(storage: &provider::TrackingStorage, group: &MlsGroup) -> Result<HashMap<Vec<u8>, zeroize::Zeroizing<Vec<u8>>>, MlsError> {
+    let encryption_keys: Vec<_> = group.own_leaf_node().map(|leaf| leaf.encryption_key()).into_iter().collect();
+    storage.group_entries(group.group_id(), &encryption_keys)
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to read group entries", &e))
+}
+
+/// Storage keys of a group's entries, including its own leaf encryption key pair
+fn stored_group_keys(storage: &provider::TrackingStorage, group: &MlsGroup) -> Result<Vec<Vec<u8>>, MlsError> {
+    let encryption_keys: Vec<_> = group.own_leaf_node().map(|leaf| leaf.encryption_key()).into_iter().collect();
+    storage.group_keys(group.group_id(), &encryption_keys)
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to read group entries", &e))
+}
+
+/// Decode a hex-encoded storage map from export_state or export_state_delta
+fn decode_storage_entries(entries: &HashMap<String, String>) -> Result<HashMap<Vec<u8>, Vec<u8>>, MlsError> {
+    entries.iter()
+        .map(|(k_hex, v_hex)| {
+            let k = hex::decode(k_hex)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid storage key hex", &e))?;
+            let v = hex::decode(v_hex)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid storage value hex", &e))?;
+            Ok((k, v))
+        })
+        .collect()
+}
+
+/// Export one group's storage entries (tree, epoch secrets, message secrets, epoch key pairs,
+/// own leaf encryption key) and the signer of our leaf, for a per-group backup or moving a single
+/// conversation to another device. Other groups are not included.
+/// Fails with InvalidState while a commit is pending: confirm or discard it first.
+#[wasm_bindgen]
+pub fn export_group_state(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    let group = take_group(&group_id)?
+        .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+    let result = BACKEND.with(|b| -> Result<String, MlsError> {
+        let backend = b.borrow();
+
+        if group.pending_commit().is_some() {
+            return Err(MlsError::new(ErrorCode::InvalidState, "Confirm or discard the pending commit before exporting the group"));
+        }
+        let own_leaf = group.own_leaf_node()
+            .ok_or_else(|| MlsError::new(ErrorCode::InvalidState, "Own leaf not found in group"))?;
+
+        let entries = stored_group_entries(backend.storage(), &group)?;
+        let storage_hex_map: HashMap<String, String> = entries.iter()
+            .map(|(k, v)| (hex::encode(k), hex::encode(v.as_slice())))
+            .collect();
+
+        let signers = state::SignerEntry::encode(storage::find_signer_json(own_leaf.signature_key().as_slice()));
+
+        let mut state = state::WasmGroupState::new(hex::encode(group.group_id().as_slice()), storage_hex_map, signers);
+        let json = serde_json::to_string(&state)
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "State serialization failed", &e));
+        // The hex copies hold the same secrets as the entries
+        state.storage.values_mut().for_each(zeroize::Zeroize::zeroize);
+        json
+    });
+
+    // Always restore group to WASM storage, even on error
+    store_group(group_id, group);
+
+    result
+}
+
+/// Import a group written by export_group_state and load it, returning its `MlsGroupState` JSON.
+/// Only that group's entries are replaced; other groups are untouched. Entries are checked before
+/// any signer is registered: anything not keyed by the group, or an encryption key pair other than
+/// its own leaf's, fails with InvalidInput. Fails with InvalidState if the group's signer conflicts
+/// with a different one already used for its identity and signature scheme.
+#[wasm_bindgen]
+pub fn import_group_state(group_json: &str) -> Result<String, MlsError> {
+    let mut group_state = state::WasmGroupState::parse(group_json)?;
+    let group_id_bytes = hex::decode(&group_state.group_id)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let group_id = GroupId::from_slice(&group_id_bytes);
+    let entries = decode_storage_entries(&group_state.storage);
+    group_state.storage.values_mut().for_each(zeroize::Zeroize::zeroize);
+    let entries = entries?;
+
+    // Load the group from the entries alone to learn its own leaf encryption key, then check
+    // that every entry belongs to it before anything (signers included) is registered
+    let staging = provider::TrackingStorage::default();
+    staging.apply(provider::StorageDelta { written: entries, deleted: Vec::new() })
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to stage group entries", &e))?;
+    let loaded = MlsGroup::load(&staging, &group_id);
+    let encryption_key = loaded.as_ref().ok().and_then(Option::as_ref)
+        .and_then(|group| group.own_leaf_node().map(|leaf| leaf.encryption_key().clone()));
+    drop(loaded);
+    let entries = staging.values.into_inner()
+        .map_err(|_| MlsError::new(ErrorCode::StorageError, "Failed to stage group entries"))?;
+    let encryption_keys: Vec<_> = encryption_key.iter().collect();
+    if encryption_keys.is_empty() {
+        return Err(MlsError::new(ErrorCode::GroupNotFound, "Imported state does not contain the group"));
+    }
+    provider::TrackingStorage::check_group_entries(&group_id, &encryption_keys, &entries)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group state", &e))?;
+
+    for entry in group_state.signers {
+        storage::add_signer_json(entry.identity_bytes()?, entry.signer)?;
+    }
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+        let storage = backend.storage();
+
+        // Entries we hold for this group, so stale epoch secrets and key pairs don't linger
+        let stale = match take_group(&group_id_bytes)? {
+            Some(group) => {
+                let stale = stored_group_keys(storage, &group);
+                store_group(group_id_bytes.clone(), group);
+                stale?
+            }
+            None => Vec::new(),
+        };
+
+        let imported: HashSet<Vec<u8>> = entries.keys().cloned().collect();
+        storage.insert_group_entries(&group_id, &encryption_keys, entries)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to write group entries", &e))?;
+        for key in stale.iter().filter(|key| !imported.contains(*key)) {
+            storage::erase_storage_value(storage, key)?;
+        }
+
+        let group = MlsGroup::load(storage, &group_id)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to load group from storage", &e))?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Imported state does not contain the group"))?;
+
+        // Signers of older group exports carry no identity: bind them to this group's leaf
+        storage::bind_unbound_signers()?;
+
+        let state = MlsGroupState::json(&group);
+
+        store_group(group_id_bytes.clone(), group);
+
+        state
+    })
+}
+
+/// Export the full WASM state encrypted for IndexedDB or sync_state, so private keys never leave
+/// WASM in the clear. The key is derived from the passkey PRF output (32 bytes) with HKDF-SHA256
+/// (info "MLS-WasmState-Encryption", as deriveKWasmState) and the state is sealed with
+/// AES-256-GCM using `user_id` as AAD. Returns base64(ciphertext || iv), the encryptString format.
+#[wasm_bindgen]
+pub fn export_state_encrypted(prf_output: &[u8], user_id: &str) -> Result<String, MlsError> {
+    let state = zeroize::Zeroizing::new(export_state()?);
+    encryption::encrypt(state.as_bytes(), prf_output, user_id)
+}
+
+/// Import state written by export_state_encrypted, or a blob the app encrypted with
+/// encryptString and deriveKWasmState, using the same PRF output and user_id.
+/// Fails with DecryptionFailed if either does not match.
+#[wasm_bindgen]
+pub fn import_state_encrypted(encrypted: &str, prf_output: &[u8], user_id: &str) -> Result<(), MlsError> {
+    let state = encryption::decrypt(encrypted, prf_output, user_id)?;
+    let state_json = std::str::from_utf8(&state)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Decrypted state is not UTF-8", &e))?;
+
+    import_state(state_json)
+}
+
+/// Current state of a loaded group as `MlsGroupState` JSON: tree hash, confirmed transcript hash,
+/// ciphersuite, member count, own leaf index, group context extensions and pending commit flag.
+#[wasm_bindgen]
+pub fn group_info(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    let group = take_group(&group_id)?
+        .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found"))?;
+
+    let result = MlsGroupState::json(&group);
+
+    // Always restore group to WASM storage, even on error
+    store_group(group_id.clone(), group);
+
+    result
+}
+
+/// Drop a group from the in-memory GROUPS map without touching backend storage, to keep
+/// memory bounded on large accounts. The next operation on it loads it again from storage.
+/// Returns whether the group was loaded.
+#[wasm_bindgen]
+pub fn unload_group(group_id_hex: &str) -> Result<bool, MlsError> {
+    let group_id = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+
+    Ok(storage::unload_group(&group_id))
+}
+
+/// A group persisted in backend storage, as listed by list_stored_groups
+#[derive(Serialize)]
+struct StoredGroup {
+    group_id: String,
+    epoch: u64,
+    /// Whether the group is already in this session's GROUPS map
+    loaded: bool,
+}
+
+/// Groups persisted in backend storage (after import_state or use_js_storage) as JSON
+/// `[{group_id, epoch, loaded}]`, so groups can be found even if the app lost its own metadata.
+#[wasm_bindgen]
+pub fn list_stored_groups() -> Result<String, MlsError> {
+    let mut groups = BACKEND.with(|b| -> Result<Vec<StoredGroup>, MlsError> {
+        let backend = b.borrow();
+        let storage = backend.storage();
+        let group_ids: Vec<GroupId> = storage.stored_group_ids()
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to list stored groups", &e))?;
+
+        group_ids.iter()
+            .map(|group_id| {
+                let context: GroupContext = storage.group_context(group_id)
+                    .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to read group context", &e))?
+                    .ok_or_else(|| MlsError::new(ErrorCode::StorageError, "Group context missing from storage"))?;
+                Ok(StoredGroup {
+                    group_id: hex::encode(group_id.as_slice()),
+                    epoch: context.epoch().as_u64(),
+                    loaded: storage::is_group_loaded(group_id.as_slice()),
+                })
+            })
+            .collect()
+    })?;
+    groups.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+
+    serde_json::to_string(&groups)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+}
+
+/// Load every group persisted in backend storage into the in-memory GROUPS map (groups already
+/// loaded are kept as they are), e.g. to warm the session up front instead of loading lazily. Returns a JSON array of `MlsGroupState`, one per group.
+#[wasm_bindgen]
+pub fn load_all_groups() -> Result<String, MlsError> {
+    let group_ids: Vec<GroupId> = BACKEND.with(|b| b.borrow().storage().stored_group_ids())
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to list stored groups", &e))?;
+
+    let mut states = Vec::with_capacity(group_ids.len());
+    for group_id in group_ids {
+        let group = take_group(group_id.as_slice())?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found in storage"))?;
+
+        let state = MlsGroupState::from_group(&group);
+
+        store_group(group_id.to_vec(), group);
+
+        states.push(state?);
+    }
+    states.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+
+    serde_json::to_string(&states)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Serialization error", &e))
+}
+
+/// Load a previously persisted MLS group from the shared backend's storage into the in-memory
+/// GROUPS map, replacing any loaded copy. Optional: every export loads a missing group on first use.
+/// group_id_hex is the MLS group ID (hex-encoded), as returned by create_group/process_welcome.
+#[wasm_bindgen]
+pub fn load_group(group_id_hex: &str) -> Result<String, MlsError> {
+    let group_id_bytes = hex::decode(group_id_hex)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group ID hex", &e))?;
+    let group_id = GroupId::from_slice(&group_id_bytes);
+
+    BACKEND.with(|b| {
+        let backend = b.borrow();
+
+        let group = MlsGroup::load(backend.storage(), &group_id)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to load group from storage", &e))?
+            .ok_or_else(|| MlsError::new(ErrorCode::GroupNotFound, "Group not found in storage"))?;
+
+        let state = MlsGroupState::json(&group);
 
         store_group(group_id_bytes.clone(), group);
 
-        serde_json::to_string(&state)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {:?}", e)))
+        state
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn json(s: &str) -> serde_json::Value {
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn with_group<T>(identity: &[u8], f: impl FnOnce(&mut MlsGroup) -> T) -> T {
+        let state: serde_json::Value = serde_json::from_str(&create_group(identity, None).unwrap()).unwrap();
+        let group_id = hex::decode(state["group_id"].as_str().unwrap()).unwrap();
+        let mut group = take_group(&group_id).unwrap().unwrap();
+        let result = f(&mut group);
+        store_group(group_id, group);
+        result
+    }
+
+    fn key_package(identity: &[u8]) -> KeyPackageBundle {
+        let ciphersuite = resolve_ciphersuite(None).unwrap();
+        build_key_package(identity, ciphersuite, resolve_lifetime(Some(3600)).unwrap(), false).unwrap()
+    }
+
+    /// Another client: a thread of its own, so it has its own BACKEND, signers and groups
+    struct Peer(mpsc::Sender<Box<dyn FnOnce() + Send>>);
+
+    impl Peer {
+        fn new() -> Self {
+            let (jobs, rx) = mpsc::channel::<Box<dyn FnOnce() + Send>>();
+            std::thread::spawn(move || rx.into_iter().for_each(|job| job()));
+            Self(jobs)
+        }
+
+        fn run<T: Send + 'static>(&self, f: impl FnOnce() -> T + Send + 'static) -> T {
+            let (result, rx) = mpsc::channel();
+            self.0.send(Box::new(move || {
+                let _ = result.send(f());
+            })).unwrap();
+            rx.recv().unwrap()
+        }
+    }
+
+    /// Key package of `identity` generated on `peer`, as hex
+    fn peer_key_package(peer: &Peer, identity: &'static [u8]) -> String {
+        peer.run(move || hex::encode(key_package(identity).key_package().tls_serialize_detached().unwrap()))
+    }
+
+    /// Add `identity` on `peer` to this thread's group and let it join from the Welcome
+    fn invite(peer: &Peer, identity: &'static [u8], group_id: &str) {
+        let commit = json(&add_member(group_id, &peer_key_package(peer, identity)).unwrap());
+        confirm_pending_commit(group_id).unwrap();
+        let welcome = commit["welcome"].as_str().unwrap().to_string();
+        peer.run(move || process_welcome(&welcome).unwrap());
+    }
+
+    /// Group created by "alice" on this thread and joined by "bob" on `bob`; returns its ID (hex)
+    fn group_with_bob(bob: &Peer) -> String {
+        let group_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        invite(bob, b"bob", &group_id);
+        group_id
+    }
+
+    // The reads_* tests pin helpers that read OpenMLS's private serde layouts, which is why
+    // Cargo.toml pins it exactly. A failure here after bumping OpenMLS means a layout changed.
+
+    #[test]
+    fn reads_queued_proposal_ref() {
+        with_group(b"alice", |group| {
+            let signer = group_signer(group).unwrap();
+            let (_, proposal_ref) = BACKEND.with(|b| {
+                group.propose_self_update(&*b.borrow(), &signer, LeafNodeParameters::default()).unwrap()
+            });
+            let queued = group.pending_proposals().next().unwrap();
+            assert_eq!(queued_proposal_ref(queued).unwrap(), proposal_ref);
+        });
+    }
+
+    #[test]
+    fn reads_ratchet_tree_leaves() {
+        let state: serde_json::Value = serde_json::from_str(&create_group(b"alice", None).unwrap()).unwrap();
+        let group_id_hex = state["group_id"].as_str().unwrap();
+        let bob = key_package(b"bob").key_package().tls_serialize_detached().unwrap();
+        add_member(group_id_hex, &hex::encode(bob)).unwrap();
+        confirm_pending_commit(group_id_hex).unwrap();
+
+        let group_id = hex::decode(group_id_hex).unwrap();
+        let group = take_group(&group_id).unwrap().unwrap();
+        let leaves: Vec<_> = ratchet_tree_leaves(&group).unwrap()
+            .into_iter()
+            .map(|(index, leaf)| (index, leaf.signature_key().as_slice().to_vec()))
+            .collect();
+        let members: Vec<_> = group.members().map(|m| (m.index.u32(), m.signature_key)).collect();
+        store_group(group_id, group);
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(leaves, members);
+    }
+
+    #[test]
+    fn reads_group_context_from_storage() {
+        with_group(b"alice", |group| {
+            let context: GroupContext = BACKEND.with(|b| b.borrow().storage().group_context(group.group_id()))
+                .unwrap()
+                .unwrap();
+            assert_eq!(context.group_id(), group.group_id());
+            assert_eq!(context.epoch(), group.epoch());
+            assert_eq!(context.extensions(), group.extensions());
+        });
+    }
+
+    #[test]
+    fn reads_unverified_lifetime() {
+        let bundle = key_package(b"bob");
+        let bytes = bundle.key_package().tls_serialize_detached().unwrap();
+        let key_package_in = KeyPackageIn::tls_deserialize(&mut bytes.as_slice()).unwrap();
+        assert_eq!(unverified_lifetime(&key_package_in), Some(*bundle.key_package().life_time()));
+    }
+
+    #[test]
+    fn queued_proposal_blocks_encrypt_until_cleared() {
+        let bob = Peer::new();
+        let group_id = group_with_bob(&bob);
+        let id = group_id.clone();
+        let proposal = bob.run(move || leave_group(&id).unwrap());
+
+        let err = decrypt(&group_id, &proposal).unwrap_err();
+        assert_eq!(err.variant.as_deref(), Some("ProposalQueued"));
+        let err = encrypt(&group_id, "hi").unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidState);
+        assert_eq!(err.variant.as_deref(), Some("GroupStateError::PendingProposal"));
+
+        clear_pending_proposals(&group_id).unwrap();
+        let message = encrypt(&group_id, "hi").unwrap();
+        let id = group_id.clone();
+        assert_eq!(bob.run(move || decrypt(&id, &message).unwrap()), "hi");
+    }
+
+    #[test]
+    fn losing_pending_commit_is_discarded_for_the_winner() {
+        let bob = Peer::new();
+        let group_id = group_with_bob(&bob);
+        self_update_commit(&group_id).unwrap();
+        let id = group_id.clone();
+        let winner = bob.run(move || {
+            let commit = json(&self_update_commit(&id).unwrap());
+            confirm_pending_commit(&id).unwrap();
+            commit["commit"].as_str().unwrap().to_string()
+        });
+
+        let processed = json(&process_message(&group_id, &winner).unwrap());
+        assert_eq!(processed["type"], "commit");
+        assert_eq!(processed["discarded_pending_commit"], true);
+
+        let message = encrypt(&group_id, "next epoch").unwrap();
+        let id = group_id.clone();
+        assert_eq!(bob.run(move || decrypt(&id, &message).unwrap()), "next epoch");
+    }
+
+    #[test]
+    fn import_group_state_rejects_foreign_entries() {
+        let group_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        let other_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        let mut blob = json(&export_group_state(&group_id).unwrap());
+        let other = json(&export_group_state(&other_id).unwrap());
+
+        // An entry of another group smuggled into the blob
+        let (key, value) = other["storage"].as_object().unwrap().iter().next().unwrap();
+        let mut injected = blob.clone();
+        injected["storage"][key] = value.clone();
+        let err = import_group_state(&injected.to_string()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+
+        // The entries of one group under the ID of another
+        blob["group_id"] = other_id.clone().into();
+        let err = import_group_state(&blob.to_string()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::GroupNotFound);
+
+        // Neither attempt touched what was already stored
+        assert_eq!(json(&export_group_state(&other_id).unwrap()), other);
+        encrypt(&group_id, "still here").unwrap();
+    }
+
+    #[test]
+    fn delete_group_erases_its_storage_entries() {
+        let group_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        let other_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        // An earlier epoch leaves key pairs OpenMLS does not delete itself
+        self_update_commit(&group_id).unwrap();
+        confirm_pending_commit(&group_id).unwrap();
+        let group = take_group(&hex::decode(&group_id).unwrap()).unwrap().unwrap();
+        let keys = BACKEND.with(|b| stored_group_keys(b.borrow().storage(), &group)).unwrap();
+        store_group(hex::decode(&group_id).unwrap(), group);
+        let other = json(&export_group_state(&other_id).unwrap());
+
+        delete_group(&group_id).unwrap();
+
+        BACKEND.with(|b| {
+            let values = b.borrow().storage().values.read().unwrap().clone();
+            assert!(keys.iter().all(|key| !values.contains_key(key)));
+        });
+        assert_eq!(encrypt(&group_id, "gone").unwrap_err().code, ErrorCode::GroupNotFound);
+        assert_eq!(load_group(&group_id).unwrap_err().code, ErrorCode::GroupNotFound);
+        assert_eq!(json(&export_group_state(&other_id).unwrap()), other);
+    }
+
+    #[test]
+    fn deleted_and_pruned_key_packages_are_gone() {
+        let hash_refs = || -> Vec<String> {
+            serde_json::from_str::<Vec<serde_json::Value>>(&list_key_packages().unwrap()).unwrap()
+                .iter()
+                .map(|kp| kp["hash_ref"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let hash_ref = |bundle: &KeyPackageBundle| BACKEND.with(|b| {
+            describe_key_package(bundle.key_package(), b.borrow().crypto()).unwrap().hash_ref
+        });
+        let ciphersuite = resolve_ciphersuite(None).unwrap();
+        let kept = hash_ref(&key_package(b"alice"));
+        let short_lived = build_key_package(b"alice", ciphersuite, Lifetime::new(60), false).unwrap();
+        let expired = hash_ref(&short_lived);
+
+        let pruned = prune_expired_key_packages(short_lived.key_package().life_time().not_after() as u32).unwrap();
+        assert_eq!(json(&pruned), serde_json::json!([expired]));
+        assert_eq!(hash_refs(), vec![kept.clone()]);
+
+        delete_key_package(&kept).unwrap();
+        assert!(hash_refs().is_empty());
+        assert_eq!(delete_key_package(&kept).unwrap_err().code, ErrorCode::NotFound);
+
+        // A Welcome for a deleted key package can no longer be processed
+        let bob = Peer::new();
+        let group_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        let commit = json(&add_member(&group_id, &peer_key_package(&bob, b"bob")).unwrap());
+        let welcome = commit["welcome"].as_str().unwrap().to_string();
+        let err = bob.run(move || {
+            let bob_ref = json(&list_key_packages().unwrap())[0]["hash_ref"].as_str().unwrap().to_string();
+            delete_key_package(&bob_ref).unwrap();
+            process_welcome(&welcome).unwrap_err()
+        });
+        assert_eq!(err.variant.as_deref(), Some("NoMatchingKeyPackage"));
+    }
+
+    #[test]
+    fn remove_member_removes_every_leaf_of_the_identity() {
+        let phone = Peer::new();
+        let group_id = group_with_bob(&phone);
+        // Bob's second device
+        let laptop = Peer::new();
+        let commit = json(&add_member(&group_id, &peer_key_package(&laptop, b"bob")).unwrap());
+        confirm_pending_commit(&group_id).unwrap();
+        let (add, welcome) = (commit["commit"].as_str().unwrap().to_string(), commit["welcome"].as_str().unwrap().to_string());
+        let id = group_id.clone();
+        phone.run(move || process_message(&id, &add).unwrap());
+        laptop.run(move || process_welcome(&welcome).unwrap());
+
+        let commit = json(&remove_member(&group_id, b"bob").unwrap());
+        confirm_pending_commit(&group_id).unwrap();
+
+        let members = json(&list_members(&group_id).unwrap());
+        assert_eq!(members.as_array().unwrap().len(), 1);
+        assert_eq!(members[0]["identity"], hex::encode(b"alice"));
+        let (id, remove) = (group_id.clone(), commit["commit"].as_str().unwrap().to_string());
+        let processed = phone.run(move || json(&process_message(&id, &remove).unwrap()));
+        assert_eq!(processed["self_removed"], true);
+
+        assert_eq!(remove_member(&group_id, b"bob").unwrap_err().code, ErrorCode::NotFound);
+        assert_eq!(remove_member(&group_id, b"alice").unwrap_err().code, ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn legacy_signers_are_bound_to_their_groups_identity() {
+        let group_id = json(&create_group(b"alice", None).unwrap())["group_id"].as_str().unwrap().to_string();
+        let state = json(&export_state().unwrap());
+        let alice_key = storage::signer_public_key(b"alice", SignatureScheme::ED25519).unwrap();
+        // Format 2 kept one signer per signature scheme, with no identity
+        let legacy = serde_json::json!({
+            "version": 2,
+            "storage_version": state["storage_version"],
+            "storage": state["storage"],
+            "signers": { (SignatureScheme::ED25519 as u16).to_string(): state["signers"][0]["signer"] },
+        });
+
+        let device = Peer::new();
+        let (signers, mallory_key) = device.run(move || {
+            import_state(&legacy.to_string()).unwrap();
+            load_group(&group_id).unwrap();
+            encrypt(&group_id, "still alice").unwrap();
+            create_group(b"mallory", None).unwrap();
+            let signers = json(&export_state().unwrap())["signers"].clone();
+            (signers, storage::signer_public_key(b"mallory", SignatureScheme::ED25519).unwrap())
+        });
+
+        let bound = signers.as_array().unwrap().iter()
+            .find(|entry| entry["identity"] == hex::encode(b"alice"))
+            .unwrap();
+        let signer: openmls_basic_credential::SignatureKeyPair = serde_json::from_str(bound["signer"].as_str().unwrap()).unwrap();
+        assert_eq!(signer.public(), alice_key.as_slice());
+        assert_ne!(mallory_key, alice_key);
+    }
+
+    #[test]
+    fn leaves_carry_the_public_key_of_an_imported_private_key() {
+        let bob = Peer::new();
+        for (scheme, ciphersuite) in [(0x0807, 0x0001), (0x0403, 0x0002)] {
+            let private_key = [7u8; 32];
+            let public_key = set_signer_from_private_key(scheme, &private_key, Some(b"alice".to_vec())).unwrap();
+            let derived = storage::derive_public_key(SignatureScheme::try_from(scheme).unwrap(), &private_key).unwrap();
+            assert_eq!(public_key, derived);
+
+            let group_id = json(&create_group(b"alice", Some(ciphersuite)).unwrap())["group_id"].as_str().unwrap().to_string();
+            let members = json(&list_members(&group_id).unwrap());
+            assert_eq!(members[0]["signature_key"], hex::encode(&public_key));
+
+            // Bob verifies our leaf's signature against that key when joining
+            let key_package = bob.run(move || {
+                let ciphersuite = resolve_ciphersuite(Some(ciphersuite)).unwrap();
+                let bundle = build_key_package(b"bob", ciphersuite, resolve_lifetime(None).unwrap(), false).unwrap();
+                hex::encode(bundle.key_package().tls_serialize_detached().unwrap())
+            });
+            let commit = json(&add_member(&group_id, &key_package).unwrap());
+            confirm_pending_commit(&group_id).unwrap();
+            let welcome = commit["welcome"].as_str().unwrap().to_string();
+            bob.run(move || process_welcome(&welcome).unwrap());
+        }
+    }
+
+    #[test]
+    fn rejects_key_package_lifetime_range_over_maximum() {
+        let ciphersuite = resolve_ciphersuite(None).unwrap();
+        let too_long = Lifetime::new(200 * 24 * 60 * 60);
+        let bundle = build_key_package(b"bob", ciphersuite, too_long, false).unwrap();
+        let bytes = bundle.key_package().tls_serialize_detached().unwrap();
+
+        let err = inspect_key_package(&bytes).unwrap_err();
+        assert_eq!(err.code, ErrorCode::PolicyViolation);
+        assert_eq!(err.variant.as_deref(), Some("KeyPackagePolicy::LifetimeTooLong"));
+    }
+
+    #[test]
+    fn welcomes_carry_the_ratchet_tree() {
+        // A group as create_group made it before the ratchet tree extension was enabled
+        let group_id = BACKEND.with(|b| {
+            let backend = b.borrow();
+            let signer = get_or_create_signer(b"alice", resolve_ciphersuite(None).unwrap()).unwrap();
+            let credential_with_key = CredentialWithKey {
+                credential: BasicCredential::new(b"alice".to_vec()).into(),
+                signature_key: signer.public().into(),
+            };
+            let group = MlsGroup::new(&*backend, &signer, &MlsGroupCreateConfig::default(), credential_with_key).unwrap();
+            let group_id = group.group_id().as_slice().to_vec();
+            store_group(group_id.clone(), group);
+            hex::encode(group_id)
+        });
+        let bob = Peer::new();
+        invite(&bob, b"bob", &group_id);
+
+        // A member who joined from a Welcome sends the tree in its own Welcomes too
+        let carol = Peer::new();
+        let key_package = peer_key_package(&carol, b"carol");
+        let id = group_id.clone();
+        let welcome = bob.run(move || {
+            let commit = json(&add_member(&id, &key_package).unwrap());
+            confirm_pending_commit(&id).unwrap();
+            commit["welcome"].as_str().unwrap().to_string()
+        });
+        let joined = carol.run(move || json(&process_welcome(&welcome).unwrap()));
+        assert_eq!(joined["group_id"], group_id.as_str());
+    }
+}
@@ -0,0 +1,143 @@
+// src/mls/wasm/src/policy.rs
+// Acceptance policy applied to key packages before they are added to a group
+
+use std::cell::RefCell;
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use openmls::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{ErrorCode, MlsError};
+
+/// Rules a key package must satisfy to be added to a group (beyond OpenMLS validation,
+/// which always rejects key packages outside their lifetime, and validate_key_package, which
+/// rejects lifetime ranges over the RFC 9420 maximum; see lifetime_violation).
+/// Extension, proposal and credential types are RFC 9420 code points; empty lists impose nothing.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyPackagePolicy {
+    /// Extension types the leaf node must list in its capabilities
+    pub required_extension_capabilities: Vec<u16>,
+    /// Proposal types the leaf node must list in its capabilities
+    pub required_proposal_capabilities: Vec<u16>,
+    /// Extension types the key package must carry (key package or leaf node extensions)
+    pub required_extensions: Vec<u16>,
+    /// Credential types accepted for the new member
+    pub allowed_credential_types: Vec<u16>,
+    /// Require the credential identity to be the user_id of its signature key,
+    /// i.e. base64url(SHA256(signature_key)) as in spec/identity_and_passkeys.md
+    pub require_identity_binding: bool,
+}
+
+// Policy in force for add_member in this WASM session (not part of export_state)
+thread_local! {
+    static KEY_PACKAGE_POLICY: RefCell<KeyPackagePolicy> = RefCell::new(KeyPackagePolicy::default());
+}
+
+pub fn set_policy(policy: KeyPackagePolicy) {
+    KEY_PACKAGE_POLICY.with(|p| *p.borrow_mut() = policy);
+}
+
+/// Check a validated key package against the session policy.
+/// Violations are `PolicyViolation` errors whose variant names the rule, e.g. "KeyPackagePolicy::Expired".
+pub fn check_key_package(key_package: &KeyPackage) -> Result<(), MlsError> {
+    KEY_PACKAGE_POLICY.with(|p| p.borrow().check(key_package))
+}
+
+/// Typed error for a key package rejected for its lifetime: "KeyPackagePolicy::LifetimeTooLong"
+/// if the range exceeds the maximum OpenMLS accepts for a leaf node, otherwise
+/// "KeyPackagePolicy::NotYetValid" or "KeyPackagePolicy::Expired"
+pub fn lifetime_violation(lifetime: &Lifetime) -> MlsError {
+    let now = now_seconds();
+    if !lifetime.has_acceptable_range() {
+        violation("LifetimeTooLong", format!(
+            "Key package lifetime range {}..{} ({} seconds) exceeds the maximum",
+            lifetime.not_before(), lifetime.not_after(), lifetime.not_after().saturating_sub(lifetime.not_before())
+        ))
+    } else if now <= lifetime.not_before() {
+        violation("NotYetValid", format!(
+            "Key package is not valid before {} (now {})", lifetime.not_before(), now
+        ))
+    } else {
+        violation("Expired", format!(
+            "Key package expired at {} (now {})", lifetime.not_after(), now
+        ))
+    }
+}
+
+impl KeyPackagePolicy {
+    fn check(&self, key_package: &KeyPackage) -> Result<(), MlsError> {
+        let leaf_node = key_package.leaf_node();
+
+        let capabilities = leaf_node.capabilities();
+        for &required in &self.required_extension_capabilities {
+            if !capabilities.extensions().iter().any(|&t| u16::from(t) == required) {
+                return Err(violation("MissingCapability", format!(
+                    "Key package does not support extension type 0x{:04x}", required
+                )));
+            }
+        }
+        for &required in &self.required_proposal_capabilities {
+            if !capabilities.proposals().iter().any(|&t| u16::from(t) == required) {
+                return Err(violation("MissingCapability", format!(
+                    "Key package does not support proposal type 0x{:04x}", required
+                )));
+            }
+        }
+
+        for &required in &self.required_extensions {
+            let present = key_package.extensions().iter()
+                .chain(leaf_node.extensions().iter())
+                .any(|e| u16::from(e.extension_type()) == required);
+            if !present {
+                return Err(violation("MissingExtension", format!(
+                    "Key package lacks extension type 0x{:04x}", required
+                )));
+            }
+        }
+
+        let credential = leaf_node.credential();
+        let credential_type = u16::from(credential.credential_type());
+        if !self.allowed_credential_types.is_empty() && !self.allowed_credential_types.contains(&credential_type) {
+            return Err(violation("CredentialType", format!(
+                "Credential type 0x{:04x} is not allowed", credential_type
+            )));
+        }
+
+        if self.require_identity_binding {
+            let user_id = user_id(leaf_node.signature_key().as_slice());
+            if credential.serialized_content() != user_id.as_bytes() {
+                return Err(violation("IdentityMismatch", format!(
+                    "Credential identity is not the user_id of its signature key (expected {})", user_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The user_id bound to a signature public key: `base64url(SHA256(public_key))` (identity_and_passkeys.md)
+pub fn user_id(public_key: &[u8]) -> String {
+    Base64UrlUnpadded::encode_string(&Sha256::digest(public_key))
+}
+
+fn violation(rule: &str, message: String) -> MlsError {
+    MlsError::with_variant(ErrorCode::PolicyViolation, &format!("KeyPackagePolicy::{}", rule), message)
+}
+
+/// Current Unix time in seconds
+fn now_seconds() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
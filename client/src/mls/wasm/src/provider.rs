@@ -1,12 +1,1189 @@
 // src/mls/wasm/src/provider.rs
-// Shared OpenMLS crypto backend for the WASM session
+// Shared OpenMLS provider for the WASM session: RustCrypto plus a change-tracking storage
+// that can write through to a JS key/value store
 
 use std::cell::RefCell;
-use openmls_rust_crypto::OpenMlsRustCrypto;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::RwLock;
+
+use js_sys::{Array, Function, Reflect, Uint8Array};
+use openmls_rust_crypto::RustCrypto;
+use openmls_traits::storage::*;
+use openmls_traits::OpenMlsProvider;
+use wasm_bindgen::{JsCast, JsValue};
+use zeroize::{Zeroize, Zeroizing};
 
 thread_local! {
     /// Shared backend instance for the WASM session.
     /// Using a shared instance ensures all group operations write to the same storage,
     /// enabling full state persistence via export_state/import_state.
-    pub static BACKEND: RefCell<OpenMlsRustCrypto> = RefCell::new(OpenMlsRustCrypto::default());
+    pub static BACKEND: RefCell<MlsProvider> = RefCell::new(MlsProvider::default());
+}
+
+/// OpenMLS provider used by every export: RustCrypto for crypto and randomness,
+/// `TrackingStorage` for state.
+#[derive(Default)]
+pub struct MlsProvider {
+    crypto: RustCrypto,
+    storage: TrackingStorage,
+}
+
+impl OpenMlsProvider for MlsProvider {
+    type CryptoProvider = RustCrypto;
+    type RandProvider = RustCrypto;
+    type StorageProvider = TrackingStorage;
+
+    fn storage(&self) -> &Self::StorageProvider {
+        &self.storage
+    }
+
+    fn crypto(&self) -> &Self::CryptoProvider {
+        &self.crypto
+    }
+
+    fn rand(&self) -> &Self::RandProvider {
+        &self.crypto
+    }
+}
+
+/// In-memory key/value storage with the same key layout as OpenMLS's `MemoryStorage`
+/// (label, then JSON key, then the big-endian storage version), so exported state stays
+/// compatible. Every write and delete made by OpenMLS is recorded until `take_changes`,
+/// which lets the app persist a small delta instead of the whole map.
+///
+/// Once a `JsStore` is attached, every mutation is written through to it before the in-memory
+/// map changes, and the map acts as a full read cache of the store.
+#[derive(Debug, Default)]
+pub struct TrackingStorage {
+    pub values: RwLock<HashMap<Vec<u8>, Vec<u8>>>,
+    changes: RwLock<Changes>,
+    js_store: RwLock<Option<JsStore>>,
+}
+
+/// Key/value store supplied by JS (use_js_storage), typically an in-memory map preloaded from
+/// IndexedDB that queues its writes back. OpenMLS storage calls are synchronous, so are the callbacks:
+/// `keys(): Uint8Array[]`, `get(key): Uint8Array | undefined`, `put(key, value)`, `delete(key)`.
+#[derive(Debug)]
+pub struct JsStore {
+    object: JsValue,
+    keys: Function,
+    get: Function,
+    put: Function,
+    delete: Function,
+}
+
+impl JsStore {
+    /// Wrap a JS object, or None if any of the four callbacks is missing.
+    pub fn new(object: JsValue) -> Option<Self> {
+        let method = |name: &str| Reflect::get(&object, &JsValue::from_str(name)).ok()?.dyn_into::<Function>().ok();
+        Some(Self {
+            keys: method("keys")?,
+            get: method("get")?,
+            put: method("put")?,
+            delete: method("delete")?,
+            object,
+        })
+    }
+
+    fn keys(&self) -> Result<Vec<Vec<u8>>, TrackingStorageError> {
+        let keys = self.keys.call0(&self.object).map_err(|_| TrackingStorageError::JsStore)?;
+        Array::from(&keys)
+            .iter()
+            .map(|key| key.dyn_into::<Uint8Array>().map(|key| key.to_vec()).map_err(|_| TrackingStorageError::JsStore))
+            .collect()
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, TrackingStorageError> {
+        let value = self.get.call1(&self.object, &Uint8Array::from(key)).map_err(|_| TrackingStorageError::JsStore)?;
+        if value.is_undefined() || value.is_null() {
+            return Ok(None);
+        }
+        value.dyn_into::<Uint8Array>()
+            .map(|value| Some(value.to_vec()))
+            .map_err(|_| TrackingStorageError::JsStore)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), TrackingStorageError> {
+        self.put.call2(&self.object, &Uint8Array::from(key), &Uint8Array::from(value))
+            .map(|_| ())
+            .map_err(|_| TrackingStorageError::JsStore)
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<(), TrackingStorageError> {
+        self.delete.call1(&self.object, &Uint8Array::from(key))
+            .map(|_| ())
+            .map_err(|_| TrackingStorageError::JsStore)
+    }
+}
+
+/// Keys touched since the last snapshot. A key is in at most one of the two sets.
+#[derive(Debug, Default)]
+struct Changes {
+    written: HashSet<Vec<u8>>,
+    deleted: HashSet<Vec<u8>>,
+}
+
+/// Storage changes since the last snapshot: current values of written keys, and deleted keys.
+#[derive(Debug, Default)]
+pub struct StorageDelta {
+    pub written: HashMap<Vec<u8>, Vec<u8>>,
+    pub deleted: Vec<Vec<u8>>,
+}
+
+/// Errors returned by `TrackingStorage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackingStorageError {
+    /// A value could not be serialized or deserialized.
+    Serialization,
+    /// A value referenced by another entry (e.g. a queued proposal) is missing.
+    MissingValue,
+    /// A storage lock was poisoned by an earlier panic.
+    LockPoisoned,
+    /// A JS store callback threw or returned something other than a Uint8Array.
+    JsStore,
+    /// An imported group entry is not keyed by that group.
+    ForeignEntry,
+    /// A JS store was attached while the in-memory map already held entries.
+    NotEmpty,
+}
+
+impl fmt::Display for TrackingStorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialization => write!(f, "Error serializing value"),
+            Self::MissingValue => write!(f, "Referenced value missing from storage"),
+            Self::LockPoisoned => write!(f, "Storage lock poisoned"),
+            Self::JsStore => write!(f, "JS storage callback failed"),
+            Self::ForeignEntry => write!(f, "Entry does not belong to the group"),
+            Self::NotEmpty => write!(f, "Storage already holds entries"),
+        }
+    }
+}
+
+impl std::error::Error for TrackingStorageError {}
+
+impl From<serde_json::Error> for TrackingStorageError {
+    fn from(_: serde_json::Error) -> Self {
+        Self::Serialization
+    }
+}
+
+impl TrackingStorage {
+    /// Attach a JS store and load its entries. The store is authoritative: attaching fails with
+    /// `NotEmpty` if memory already holds entries, which could be older than the store's and
+    /// would mix epochs of a group. Legacy snapshots are imported after attaching instead.
+    pub fn attach(&self, store: JsStore) -> Result<(), TrackingStorageError> {
+        let mut values = self.values.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        if !values.is_empty() || self.js_store.read().map_err(|_| TrackingStorageError::LockPoisoned)?.is_some() {
+            return Err(TrackingStorageError::NotEmpty);
+        }
+        for key in store.keys()? {
+            if let Some(value) = store.get(&key)? {
+                values.insert(key, value);
+            }
+        }
+        drop(values);
+        *self.js_store.write().map_err(|_| TrackingStorageError::LockPoisoned)? = Some(store);
+        Ok(())
+    }
+
+    /// Replace the whole map (import_state). The new contents are the snapshot, so nothing is tracked.
+    pub fn replace(&self, entries: HashMap<Vec<u8>, Vec<u8>>) -> Result<(), TrackingStorageError> {
+        let mut values = self.values.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        for key in values.keys().filter(|key| !entries.contains_key(*key)) {
+            self.js_delete(key)?;
+        }
+        for (key, value) in &entries {
+            self.js_put(key, value)?;
+        }
+        for value in values.values_mut() {
+            value.zeroize();
+        }
+        *values = entries;
+        drop(values);
+        self.clear_changes()
+    }
+
+    /// Apply a delta on top of the current map without tracking it (apply_state_delta).
+    pub fn apply(&self, delta: StorageDelta) -> Result<(), TrackingStorageError> {
+        for key in &delta.deleted {
+            self.js_delete(key)?;
+        }
+        for (key, value) in &delta.written {
+            self.js_put(key, value)?;
+        }
+        let mut values = self.values.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        for key in &delta.deleted {
+            if let Some(mut value) = values.remove(key) {
+                value.zeroize();
+            }
+        }
+        values.extend(delta.written);
+        Ok(())
+    }
+
+    /// Changes since the last snapshot, resetting tracking so the next delta starts here.
+    pub fn take_changes(&self) -> Result<StorageDelta, TrackingStorageError> {
+        let values = self.values.read().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        let mut changes = self.changes.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        let changes = std::mem::take(&mut *changes);
+        Ok(StorageDelta {
+            written: changes.written
+                .into_iter()
+                .filter_map(|key| values.get(&key).map(|value| (key, value.clone())))
+                .collect(),
+            deleted: changes.deleted.into_iter().collect(),
+        })
+    }
+
+    /// Forget tracked changes, e.g. after a full export_state.
+    pub fn clear_changes(&self) -> Result<(), TrackingStorageError> {
+        let mut changes = self.changes.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        *changes = Changes::default();
+        Ok(())
+    }
+
+    /// IDs of all groups persisted in storage: every MlsGroup writes a join config keyed by its ID.
+    pub fn stored_group_ids<GroupId: serde::de::DeserializeOwned>(&self) -> Result<Vec<GroupId>, TrackingStorageError> {
+        let version = u16::to_be_bytes(CURRENT_VERSION);
+        let values = self.values.read().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        values.keys()
+            .filter_map(|key| key.strip_prefix(JOIN_CONFIG_LABEL)?.strip_suffix(&version))
+            .map(|id| Ok(serde_json::from_slice(id)?))
+            .collect()
+    }
+
+    /// Storage keys of one group's entries: everything keyed by its ID, its queued proposals and
+    /// epoch key pairs, and the leaf encryption key pairs for `encryption_keys`.
+    pub fn group_keys(
+        &self,
+        group_id: &impl traits::GroupId<CURRENT_VERSION>,
+        encryption_keys: &[&impl traits::EncryptionKey<CURRENT_VERSION>],
+    ) -> Result<Vec<Vec<u8>>, TrackingStorageError> {
+        let group_keys = GroupKeys::new(group_id, encryption_keys)?;
+        let values = self.values.read().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        Ok(values.keys()
+            .filter(|key| group_keys.matches(key))
+            .cloned()
+            .collect())
+    }
+
+    /// Entries belonging to one group (see `group_keys`). The copied values hold the group's
+    /// secrets and are zeroized when dropped.
+    pub fn group_entries(
+        &self,
+        group_id: &impl traits::GroupId<CURRENT_VERSION>,
+        encryption_keys: &[&impl traits::EncryptionKey<CURRENT_VERSION>],
+    ) -> Result<HashMap<Vec<u8>, Zeroizing<Vec<u8>>>, TrackingStorageError> {
+        let group_keys = GroupKeys::new(group_id, encryption_keys)?;
+        let values = self.values.read().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        Ok(values.iter()
+            .filter(|(key, _)| group_keys.matches(key))
+            .map(|(key, value)| (key.clone(), Zeroizing::new(value.clone())))
+            .collect())
+    }
+
+    /// Check that every entry of an imported group belongs to it (see `group_keys`), with
+    /// `encryption_keys` its own leaf's: a blob must not overwrite another group's or a key
+    /// package's encryption key pair.
+    pub fn check_group_entries(
+        group_id: &impl traits::GroupId<CURRENT_VERSION>,
+        encryption_keys: &[&impl traits::EncryptionKey<CURRENT_VERSION>],
+        entries: &HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<(), TrackingStorageError> {
+        let group_keys = GroupKeys::new(group_id, encryption_keys)?;
+        if !entries.keys().all(|key| group_keys.matches(key)) {
+            return Err(TrackingStorageError::ForeignEntry);
+        }
+        Ok(())
+    }
+
+    /// Write one group's entries (import_group_state), tracked and written through like OpenMLS
+    /// writes. Fails before writing anything if check_group_entries does.
+    pub fn insert_group_entries(
+        &self,
+        group_id: &impl traits::GroupId<CURRENT_VERSION>,
+        encryption_keys: &[&impl traits::EncryptionKey<CURRENT_VERSION>],
+        entries: HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Result<(), TrackingStorageError> {
+        Self::check_group_entries(group_id, encryption_keys, &entries)?;
+        for (key, value) in entries {
+            self.put(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Remove an entry, zeroizing its bytes (key packages and epoch secrets carry private keys).
+    pub fn erase(&self, key: &[u8]) -> Result<(), TrackingStorageError> {
+        if let Some(mut value) = self.remove(key)? {
+            value.zeroize();
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, TrackingStorageError> {
+        let values = self.values.read().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        Ok(values.get(key).cloned())
+    }
+
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrackingStorageError> {
+        self.js_put(&key, &value)?;
+        let mut values = self.values.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        let mut changes = self.changes.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        changes.deleted.remove(&key);
+        changes.written.insert(key.clone());
+        values.insert(key, value);
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Vec<u8>>, TrackingStorageError> {
+        self.js_delete(key)?;
+        let mut values = self.values.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        let mut changes = self.changes.write().map_err(|_| TrackingStorageError::LockPoisoned)?;
+        changes.written.remove(key);
+        changes.deleted.insert(key.to_vec());
+        Ok(values.remove(key))
+    }
+
+    fn js_put(&self, key: &[u8], value: &[u8]) -> Result<(), TrackingStorageError> {
+        match &*self.js_store.read().map_err(|_| TrackingStorageError::LockPoisoned)? {
+            Some(store) => store.put(key, value),
+            None => Ok(()),
+        }
+    }
+
+    fn js_delete(&self, key: &[u8]) -> Result<(), TrackingStorageError> {
+        match &*self.js_store.read().map_err(|_| TrackingStorageError::LockPoisoned)? {
+            Some(store) => store.delete(key),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&self, label: &[u8], key: &[u8], value: Vec<u8>) -> Result<(), TrackingStorageError> {
+        self.put(storage_key(label, key), value)
+    }
+
+    fn append(&self, label: &[u8], key: &[u8], value: Vec<u8>) -> Result<(), TrackingStorageError> {
+        let storage_key = storage_key(label, key);
+        let mut list: Vec<Vec<u8>> = match self.get(&storage_key)? {
+            Some(list_bytes) => serde_json::from_slice(&list_bytes)?,
+            None => vec![],
+        };
+        list.push(value);
+        self.put(storage_key, serde_json::to_vec(&list)?)
+    }
+
+    fn remove_item(&self, label: &[u8], key: &[u8], value: Vec<u8>) -> Result<(), TrackingStorageError> {
+        let storage_key = storage_key(label, key);
+        let mut list: Vec<Vec<u8>> = match self.get(&storage_key)? {
+            Some(list_bytes) => serde_json::from_slice(&list_bytes)?,
+            None => vec![],
+        };
+        if let Some(pos) = list.iter().position(|stored_item| stored_item == &value) {
+            list.remove(pos);
+        }
+        self.put(storage_key, serde_json::to_vec(&list)?)
+    }
+
+    fn read<V: serde::de::DeserializeOwned>(&self, label: &[u8], key: &[u8]) -> Result<Option<V>, TrackingStorageError> {
+        match self.get(&storage_key(label, key))? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn read_list<V: serde::de::DeserializeOwned>(&self, label: &[u8], key: &[u8]) -> Result<Vec<V>, TrackingStorageError> {
+        let list: Vec<Vec<u8>> = match self.get(&storage_key(label, key))? {
+            Some(list_bytes) => serde_json::from_slice(&list_bytes)?,
+            None => vec![],
+        };
+        list.iter()
+            .map(|value_bytes| serde_json::from_slice(value_bytes).map_err(TrackingStorageError::from))
+            .collect()
+    }
+
+    fn delete(&self, label: &[u8], key: &[u8]) -> Result<(), TrackingStorageError> {
+        self.erase(&storage_key(label, key))
+    }
+}
+
+// Storage labels, identical to openmls_memory_storage 0.4.1's
+pub const KEY_PACKAGE_LABEL: &[u8] = b"KeyPackage";
+const PSK_LABEL: &[u8] = b"Psk";
+const ENCRYPTION_KEY_PAIR_LABEL: &[u8] = b"EncryptionKeyPair";
+const SIGNATURE_KEY_PAIR_LABEL: &[u8] = b"SignatureKeyPair";
+const EPOCH_KEY_PAIRS_LABEL: &[u8] = b"EpochKeyPairs";
+
+// related to PublicGroup
+const TREE_LABEL: &[u8] = b"Tree";
+const GROUP_CONTEXT_LABEL: &[u8] = b"GroupContext";
+const INTERIM_TRANSCRIPT_HASH_LABEL: &[u8] = b"InterimTranscriptHash";
+const CONFIRMATION_TAG_LABEL: &[u8] = b"ConfirmationTag";
+
+// related to MlsGroup
+const JOIN_CONFIG_LABEL: &[u8] = b"MlsGroupJoinConfig";
+const OWN_LEAF_NODES_LABEL: &[u8] = b"OwnLeafNodes";
+const GROUP_STATE_LABEL: &[u8] = b"GroupState";
+const QUEUED_PROPOSAL_LABEL: &[u8] = b"QueuedProposal";
+const PROPOSAL_QUEUE_REFS_LABEL: &[u8] = b"ProposalQueueRefs";
+const OWN_LEAF_NODE_INDEX_LABEL: &[u8] = b"OwnLeafNodeIndex";
+const EPOCH_SECRETS_LABEL: &[u8] = b"EpochSecrets";
+const RESUMPTION_PSK_STORE_LABEL: &[u8] = b"ResumptionPsk";
+const MESSAGE_SECRETS_LABEL: &[u8] = b"MessageSecrets";
+
+/// Labels of entries keyed by the group ID alone
+const GROUP_LABELS: &[&[u8]] = &[
+    TREE_LABEL,
+    GROUP_CONTEXT_LABEL,
+    INTERIM_TRANSCRIPT_HASH_LABEL,
+    CONFIRMATION_TAG_LABEL,
+    JOIN_CONFIG_LABEL,
+    OWN_LEAF_NODES_LABEL,
+    GROUP_STATE_LABEL,
+    PROPOSAL_QUEUE_REFS_LABEL,
+    OWN_LEAF_NODE_INDEX_LABEL,
+    EPOCH_SECRETS_LABEL,
+    RESUMPTION_PSK_STORE_LABEL,
+    MESSAGE_SECRETS_LABEL,
+];
+
+/// Matches the storage keys of one group's entries, including the leaf encryption key pairs
+/// (keyed by public key) it was built with
+struct GroupKeys {
+    keys: HashSet<Vec<u8>>,
+    prefixes: [Vec<u8>; 2],
+}
+
+impl GroupKeys {
+    fn new(
+        group_id: &impl traits::GroupId<CURRENT_VERSION>,
+        encryption_keys: &[&impl traits::EncryptionKey<CURRENT_VERSION>],
+    ) -> Result<Self, TrackingStorageError> {
+        let id = serde_json::to_vec(group_id)?;
+        let mut keys: HashSet<Vec<u8>> = GROUP_LABELS.iter().map(|label| storage_key(label, &id)).collect();
+        for encryption_key in encryption_keys {
+            keys.insert(storage_key(ENCRYPTION_KEY_PAIR_LABEL, &serde_json::to_vec(encryption_key)?));
+        }
+        Ok(Self {
+            keys,
+            // Epoch key pairs are keyed by (group ID, epoch, leaf index) JSON, queued proposals by
+            // the JSON array [group ID, proposal ref]
+            prefixes: [
+                [EPOCH_KEY_PAIRS_LABEL, &id].concat(),
+                [QUEUED_PROPOSAL_LABEL, b"[", &id, b","].concat(),
+            ],
+        })
+    }
+
+    fn matches(&self, key: &[u8]) -> bool {
+        self.keys.contains(key) || self.prefixes.iter().any(|prefix| key.starts_with(prefix))
+    }
+}
+
+/// Build a storage key from label, key and the current storage version.
+fn storage_key(label: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut storage_key = label.to_vec();
+    storage_key.extend_from_slice(key);
+    storage_key.extend_from_slice(&u16::to_be_bytes(CURRENT_VERSION));
+    storage_key
+}
+
+fn epoch_key_pairs_id(
+    group_id: &impl traits::GroupId<CURRENT_VERSION>,
+    epoch: &impl traits::EpochKey<CURRENT_VERSION>,
+    leaf_index: u32,
+) -> Result<Vec<u8>, TrackingStorageError> {
+    let mut key = serde_json::to_vec(group_id)?;
+    key.extend_from_slice(&serde_json::to_vec(epoch)?);
+    key.extend_from_slice(&serde_json::to_vec(&leaf_index)?);
+    Ok(key)
+}
+
+// Ported from openmls_memory_storage 0.4.1 (`impl StorageProvider for MemoryStorage`); keep the
+// keys and serialization in step with it when the pinned OpenMLS version changes.
+impl StorageProvider<CURRENT_VERSION> for TrackingStorage {
+    type Error = TrackingStorageError;
+
+    fn queue_proposal<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+        QueuedProposal: traits::QueuedProposal<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        proposal_ref: &ProposalRef,
+        proposal: &QueuedProposal,
+    ) -> Result<(), Self::Error> {
+        // write proposal to key (group_id, proposal_ref)
+        let key = serde_json::to_vec(&(group_id, proposal_ref))?;
+        let value = serde_json::to_vec(proposal)?;
+        self.write(QUEUED_PROPOSAL_LABEL, &key, value)?;
+
+        // update proposal list for group_id
+        let key = serde_json::to_vec(group_id)?;
+        let value = serde_json::to_vec(proposal_ref)?;
+        self.append(PROPOSAL_QUEUE_REFS_LABEL, &key, value)?;
+
+        Ok(())
+    }
+
+    fn write_tree<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        TreeSync: traits::TreeSync<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        tree: &TreeSync,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            TREE_LABEL,
+            &serde_json::to_vec(&group_id)?,
+            serde_json::to_vec(&tree)?,
+        )
+    }
+
+    fn write_interim_transcript_hash<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        interim_transcript_hash: &InterimTranscriptHash,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            INTERIM_TRANSCRIPT_HASH_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(interim_transcript_hash)?,
+        )
+    }
+
+    fn write_context<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupContext: traits::GroupContext<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        group_context: &GroupContext,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            GROUP_CONTEXT_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(group_context)?,
+        )
+    }
+
+    fn write_confirmation_tag<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ConfirmationTag: traits::ConfirmationTag<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        confirmation_tag: &ConfirmationTag,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            CONFIRMATION_TAG_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(confirmation_tag)?,
+        )
+    }
+
+    fn write_signature_key_pair<
+        SignaturePublicKey: traits::SignaturePublicKey<CURRENT_VERSION>,
+        SignatureKeyPair: traits::SignatureKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &SignaturePublicKey,
+        signature_key_pair: &SignatureKeyPair,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            SIGNATURE_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key)?,
+            serde_json::to_vec(signature_key_pair)?,
+        )
+    }
+
+    fn queued_proposal_refs<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Vec<ProposalRef>, Self::Error> {
+        self.read_list(PROPOSAL_QUEUE_REFS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn queued_proposals<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+        QueuedProposal: traits::QueuedProposal<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Vec<(ProposalRef, QueuedProposal)>, Self::Error> {
+        let refs: Vec<ProposalRef> =
+            self.read_list(PROPOSAL_QUEUE_REFS_LABEL, &serde_json::to_vec(group_id)?)?;
+
+        refs.into_iter()
+            .map(|proposal_ref| -> Result<_, _> {
+                let key = (group_id, &proposal_ref);
+                let key = serde_json::to_vec(&key)?;
+
+                let proposal = self
+                    .read(QUEUED_PROPOSAL_LABEL, &key)?
+                    .ok_or(TrackingStorageError::MissingValue)?;
+                Ok((proposal_ref, proposal))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    fn tree<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        TreeSync: traits::TreeSync<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<TreeSync>, Self::Error> {
+        self.read(TREE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn group_context<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupContext: traits::GroupContext<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<GroupContext>, Self::Error> {
+        self.read(GROUP_CONTEXT_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn interim_transcript_hash<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        InterimTranscriptHash: traits::InterimTranscriptHash<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<InterimTranscriptHash>, Self::Error> {
+        self.read(INTERIM_TRANSCRIPT_HASH_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn confirmation_tag<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ConfirmationTag: traits::ConfirmationTag<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<ConfirmationTag>, Self::Error> {
+        self.read(CONFIRMATION_TAG_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn signature_key_pair<
+        SignaturePublicKey: traits::SignaturePublicKey<CURRENT_VERSION>,
+        SignatureKeyPair: traits::SignatureKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &SignaturePublicKey,
+    ) -> Result<Option<SignatureKeyPair>, Self::Error> {
+        self.read(SIGNATURE_KEY_PAIR_LABEL, &serde_json::to_vec(public_key)?)
+    }
+
+    fn write_key_package<
+        HashReference: traits::HashReference<CURRENT_VERSION>,
+        KeyPackage: traits::KeyPackage<CURRENT_VERSION>,
+    >(
+        &self,
+        hash_ref: &HashReference,
+        key_package: &KeyPackage,
+    ) -> Result<(), Self::Error> {
+        let key = serde_json::to_vec(&hash_ref)?;
+        let value = serde_json::to_vec(&key_package)?;
+
+        self.write(KEY_PACKAGE_LABEL, &key, value)
+    }
+
+    fn write_psk<
+        PskId: traits::PskId<CURRENT_VERSION>,
+        PskBundle: traits::PskBundle<CURRENT_VERSION>,
+    >(
+        &self,
+        psk_id: &PskId,
+        psk: &PskBundle,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            PSK_LABEL,
+            &serde_json::to_vec(&psk_id)?,
+            serde_json::to_vec(&psk)?,
+        )
+    }
+
+    fn write_encryption_key_pair<
+        EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &EncryptionKey,
+        key_pair: &HpkeKeyPair,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key)?,
+            serde_json::to_vec(key_pair)?,
+        )
+    }
+
+    fn key_package<
+        KeyPackageRef: traits::HashReference<CURRENT_VERSION>,
+        KeyPackage: traits::KeyPackage<CURRENT_VERSION>,
+    >(
+        &self,
+        hash_ref: &KeyPackageRef,
+    ) -> Result<Option<KeyPackage>, Self::Error> {
+        let key = serde_json::to_vec(&hash_ref)?;
+        self.read(KEY_PACKAGE_LABEL, &key)
+    }
+
+    fn psk<PskBundle: traits::PskBundle<CURRENT_VERSION>, PskId: traits::PskId<CURRENT_VERSION>>(
+        &self,
+        psk_id: &PskId,
+    ) -> Result<Option<PskBundle>, Self::Error> {
+        self.read(PSK_LABEL, &serde_json::to_vec(&psk_id)?)
+    }
+
+    fn encryption_key_pair<
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+        EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &EncryptionKey,
+    ) -> Result<Option<HpkeKeyPair>, Self::Error> {
+        self.read(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key)?,
+        )
+    }
+
+    fn delete_signature_key_pair<
+        SignaturePublicKey: traits::SignaturePublicKey<CURRENT_VERSION>,
+    >(
+        &self,
+        public_key: &SignaturePublicKey,
+    ) -> Result<(), Self::Error> {
+        self.delete(
+            SIGNATURE_KEY_PAIR_LABEL,
+            &serde_json::to_vec(public_key)?,
+        )
+    }
+
+    fn delete_encryption_key_pair<EncryptionKey: traits::EncryptionKey<CURRENT_VERSION>>(
+        &self,
+        public_key: &EncryptionKey,
+    ) -> Result<(), Self::Error> {
+        self.delete(
+            ENCRYPTION_KEY_PAIR_LABEL,
+            &serde_json::to_vec(&public_key)?,
+        )
+    }
+
+    fn delete_key_package<KeyPackageRef: traits::HashReference<CURRENT_VERSION>>(
+        &self,
+        hash_ref: &KeyPackageRef,
+    ) -> Result<(), Self::Error> {
+        self.delete(KEY_PACKAGE_LABEL, &serde_json::to_vec(&hash_ref)?)
+    }
+
+    fn delete_psk<PskKey: traits::PskId<CURRENT_VERSION>>(
+        &self,
+        psk_id: &PskKey,
+    ) -> Result<(), Self::Error> {
+        self.delete(PSK_LABEL, &serde_json::to_vec(&psk_id)?)
+    }
+
+    fn group_state<
+        GroupState: traits::GroupState<CURRENT_VERSION>,
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<GroupState>, Self::Error> {
+        self.read(GROUP_STATE_LABEL, &serde_json::to_vec(&group_id)?)
+    }
+
+    fn write_group_state<
+        GroupState: traits::GroupState<CURRENT_VERSION>,
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        group_state: &GroupState,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            GROUP_STATE_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(group_state)?,
+        )
+    }
+
+    fn delete_group_state<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(GROUP_STATE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn message_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<MessageSecrets>, Self::Error> {
+        self.read(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_message_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MessageSecrets: traits::MessageSecrets<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        message_secrets: &MessageSecrets,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            MESSAGE_SECRETS_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(message_secrets)?,
+        )
+    }
+
+    fn delete_message_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(MESSAGE_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn resumption_psk_store<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ResumptionPskStore: traits::ResumptionPskStore<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<ResumptionPskStore>, Self::Error> {
+        self.read(RESUMPTION_PSK_STORE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_resumption_psk_store<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ResumptionPskStore: traits::ResumptionPskStore<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        resumption_psk_store: &ResumptionPskStore,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            RESUMPTION_PSK_STORE_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(resumption_psk_store)?,
+        )
+    }
+
+    fn delete_all_resumption_psk_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(RESUMPTION_PSK_STORE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn own_leaf_index<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LeafNodeIndex: traits::LeafNodeIndex<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<LeafNodeIndex>, Self::Error> {
+        self.read(OWN_LEAF_NODE_INDEX_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_own_leaf_index<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LeafNodeIndex: traits::LeafNodeIndex<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        own_leaf_index: &LeafNodeIndex,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            OWN_LEAF_NODE_INDEX_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(own_leaf_index)?,
+        )
+    }
+
+    fn delete_own_leaf_index<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(OWN_LEAF_NODE_INDEX_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn group_epoch_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupEpochSecrets: traits::GroupEpochSecrets<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<GroupEpochSecrets>, Self::Error> {
+        self.read(EPOCH_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_group_epoch_secrets<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        GroupEpochSecrets: traits::GroupEpochSecrets<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        group_epoch_secrets: &GroupEpochSecrets,
+    ) -> Result<(), Self::Error> {
+        self.write(
+            EPOCH_SECRETS_LABEL,
+            &serde_json::to_vec(group_id)?,
+            serde_json::to_vec(group_epoch_secrets)?,
+        )
+    }
+
+    fn delete_group_epoch_secrets<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(EPOCH_SECRETS_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_encryption_epoch_key_pairs<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        EpochKey: traits::EpochKey<CURRENT_VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        epoch: &EpochKey,
+        leaf_index: u32,
+        key_pairs: &[HpkeKeyPair],
+    ) -> Result<(), Self::Error> {
+        let key = epoch_key_pairs_id(group_id, epoch, leaf_index)?;
+        let value = serde_json::to_vec(key_pairs)?;
+
+        self.write(EPOCH_KEY_PAIRS_LABEL, &key, value)
+    }
+
+    fn encryption_epoch_key_pairs<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        EpochKey: traits::EpochKey<CURRENT_VERSION>,
+        HpkeKeyPair: traits::HpkeKeyPair<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        epoch: &EpochKey,
+        leaf_index: u32,
+    ) -> Result<Vec<HpkeKeyPair>, Self::Error> {
+        let key = epoch_key_pairs_id(group_id, epoch, leaf_index)?;
+        match self.get(&storage_key(EPOCH_KEY_PAIRS_LABEL, &key))? {
+            Some(value) => Ok(serde_json::from_slice(&value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn delete_encryption_epoch_key_pairs<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        EpochKey: traits::EpochKey<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        epoch: &EpochKey,
+        leaf_index: u32,
+    ) -> Result<(), Self::Error> {
+        let key = epoch_key_pairs_id(group_id, epoch, leaf_index)?;
+        self.delete(EPOCH_KEY_PAIRS_LABEL, &key)
+    }
+
+    fn clear_proposal_queue<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        // Get all proposal refs for this group.
+        let proposal_refs: Vec<ProposalRef> =
+            self.read_list(PROPOSAL_QUEUE_REFS_LABEL, &serde_json::to_vec(group_id)?)?;
+        for proposal_ref in proposal_refs {
+            // Delete all proposals.
+            let key = serde_json::to_vec(&(group_id, proposal_ref))?;
+            self.delete(QUEUED_PROPOSAL_LABEL, &key)?;
+        }
+
+        // Delete the proposal refs from the store.
+        self.delete(PROPOSAL_QUEUE_REFS_LABEL, &serde_json::to_vec(group_id)?)?;
+
+        Ok(())
+    }
+
+    fn mls_group_join_config<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Option<MlsGroupJoinConfig>, Self::Error> {
+        self.read(JOIN_CONFIG_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn write_mls_join_config<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        MlsGroupJoinConfig: traits::MlsGroupJoinConfig<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        config: &MlsGroupJoinConfig,
+    ) -> Result<(), Self::Error> {
+        let key = serde_json::to_vec(group_id)?;
+        let value = serde_json::to_vec(config)?;
+
+        self.write(JOIN_CONFIG_LABEL, &key, value)
+    }
+
+    fn own_leaf_nodes<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LeafNode: traits::LeafNode<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<Vec<LeafNode>, Self::Error> {
+        self.read_list(OWN_LEAF_NODES_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn append_own_leaf_node<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        LeafNode: traits::LeafNode<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        leaf_node: &LeafNode,
+    ) -> Result<(), Self::Error> {
+        let key = serde_json::to_vec(group_id)?;
+        let value = serde_json::to_vec(leaf_node)?;
+        self.append(OWN_LEAF_NODES_LABEL, &key, value)
+    }
+
+    fn delete_own_leaf_nodes<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(OWN_LEAF_NODES_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn delete_group_config<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(JOIN_CONFIG_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn delete_tree<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(TREE_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn delete_confirmation_tag<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(
+            CONFIRMATION_TAG_LABEL,
+            &serde_json::to_vec(group_id)?,
+        )
+    }
+
+    fn delete_context<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(GROUP_CONTEXT_LABEL, &serde_json::to_vec(group_id)?)
+    }
+
+    fn delete_interim_transcript_hash<GroupId: traits::GroupId<CURRENT_VERSION>>(
+        &self,
+        group_id: &GroupId,
+    ) -> Result<(), Self::Error> {
+        self.delete(
+            INTERIM_TRANSCRIPT_HASH_LABEL,
+            &serde_json::to_vec(group_id)?,
+        )
+    }
+
+    fn remove_proposal<
+        GroupId: traits::GroupId<CURRENT_VERSION>,
+        ProposalRef: traits::ProposalRef<CURRENT_VERSION>,
+    >(
+        &self,
+        group_id: &GroupId,
+        proposal_ref: &ProposalRef,
+    ) -> Result<(), Self::Error> {
+        let key = serde_json::to_vec(group_id)?;
+        let value = serde_json::to_vec(proposal_ref)?;
+
+        self.remove_item(PROPOSAL_QUEUE_REFS_LABEL, &key, value)?;
+
+        let key = serde_json::to_vec(&(group_id, proposal_ref))?;
+        self.delete(QUEUED_PROPOSAL_LABEL, &key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(storage: &TrackingStorage) -> HashMap<Vec<u8>, Vec<u8>> {
+        storage.values.read().unwrap().clone()
+    }
+
+    #[test]
+    fn delta_replays_writes_and_deletes() {
+        let base = HashMap::from([(b"old".to_vec(), b"0".to_vec()), (b"kept".to_vec(), b"0".to_vec())]);
+        let storage = TrackingStorage::default();
+        storage.replace(base.clone()).unwrap();
+
+        storage.put(b"old".to_vec(), b"1".to_vec()).unwrap();
+        storage.erase(b"old").unwrap();
+        storage.put(b"temp".to_vec(), b"1".to_vec()).unwrap();
+        storage.erase(b"temp").unwrap();
+        storage.erase(b"new").unwrap();
+        storage.put(b"new".to_vec(), b"2".to_vec()).unwrap();
+
+        let mut delta = storage.take_changes().unwrap();
+        delta.deleted.sort();
+        assert_eq!(delta.written, HashMap::from([(b"new".to_vec(), b"2".to_vec())]));
+        assert_eq!(delta.deleted, [b"old".to_vec(), b"temp".to_vec()]);
+
+        let replica = TrackingStorage::default();
+        replica.replace(base).unwrap();
+        replica.apply(delta).unwrap();
+        assert_eq!(entries(&replica), entries(&storage));
+    }
+
+    #[test]
+    fn take_changes_resets_tracking() {
+        let storage = TrackingStorage::default();
+        storage.put(b"key".to_vec(), b"1".to_vec()).unwrap();
+        assert_eq!(storage.take_changes().unwrap().written.len(), 1);
+
+        let delta = storage.take_changes().unwrap();
+        assert!(delta.written.is_empty() && delta.deleted.is_empty());
+
+        // apply_state_delta input is not tracked again
+        storage.apply(StorageDelta { written: HashMap::from([(b"key".to_vec(), b"2".to_vec())]), deleted: Vec::new() }).unwrap();
+        let delta = storage.take_changes().unwrap();
+        assert!(delta.written.is_empty() && delta.deleted.is_empty());
+    }
 }
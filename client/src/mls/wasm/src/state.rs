@@ -0,0 +1,291 @@
+// src/mls/wasm/src/state.rs
+// Versioned format of export_state / export_state_delta / export_group_state output,
+// with migrations from older formats
+
+use std::collections::HashMap;
+
+use openmls_basic_credential::SignatureKeyPair;
+use openmls_traits::storage::CURRENT_VERSION;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{ErrorCode, MlsError};
+
+/// Version of the exported state layout. Bump it and add a step to `upgrade` whenever the
+/// layout changes, so states saved by older builds keep importing.
+///
+/// 1: `{storage, signer}` or `{storage, signers}`, no version fields
+/// 2: `{version, storage_version, storage, signers}` (deltas add `deleted`), signers keyed by scheme
+/// 3: signers as a list of `{identity, signer}`, one per identity and scheme
+pub const STATE_FORMAT_VERSION: u32 = 3;
+
+/// OpenMLS storage version of every state exported before versioning (OpenMLS 0.7)
+const UNVERSIONED_STORAGE_VERSION: u16 = 1;
+
+/// Full backend state, as written by export_state
+#[derive(Serialize, Deserialize)]
+pub struct WasmState {
+    pub version: u32,
+    /// OpenMLS storage version the keys and values were written with
+    pub storage_version: u16,
+    /// Hex storage key -> hex value
+    pub storage: HashMap<String, String>,
+    pub signers: Vec<SignerEntry>,
+}
+
+/// Storage changes since the previous export, as written by export_state_delta
+#[derive(Serialize, Deserialize)]
+pub struct WasmStateDelta {
+    pub version: u32,
+    pub storage_version: u16,
+    /// Hex storage key -> hex value, for keys written since the previous export
+    pub storage: HashMap<String, String>,
+    /// Hex storage keys deleted since the previous export
+    #[serde(default)]
+    pub deleted: Vec<String>,
+    pub signers: Vec<SignerEntry>,
+}
+
+/// One group's storage entries and its signer, as written by export_group_state
+#[derive(Serialize, Deserialize)]
+pub struct WasmGroupState {
+    pub version: u32,
+    pub storage_version: u16,
+    /// MLS group ID (hex)
+    pub group_id: String,
+    /// Hex storage key -> hex value, for this group's entries only
+    pub storage: HashMap<String, String>,
+    /// The signer of our leaf in this group
+    pub signers: Vec<SignerEntry>,
+}
+
+/// A serialized signer and the credential identity it belongs to
+#[derive(Serialize, Deserialize)]
+pub struct SignerEntry {
+    /// Credential identity (hex); null for signers migrated from format 2, which are bound to
+    /// the first identity that uses them
+    pub identity: Option<String>,
+    /// Serialized SignatureKeyPair
+    pub signer: String,
+}
+
+impl SignerEntry {
+    /// Hex-encode registry entries from storage::get_signer_jsons
+    pub fn encode(signers: impl IntoIterator<Item = (Option<Vec<u8>>, String)>) -> Vec<Self> {
+        signers.into_iter()
+            .map(|(identity, signer)| Self { identity: identity.map(hex::encode), signer })
+            .collect()
+    }
+
+    pub fn identity_bytes(&self) -> Result<Option<Vec<u8>>, MlsError> {
+        self.identity.as_deref()
+            .map(hex::decode)
+            .transpose()
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid signer identity hex", &e))
+    }
+}
+
+impl WasmState {
+    pub fn new(storage: HashMap<String, String>, signers: Vec<SignerEntry>) -> Self {
+        Self { version: STATE_FORMAT_VERSION, storage_version: CURRENT_VERSION, storage, signers }
+    }
+
+    /// Parse a state written by this or any earlier build, migrating it to the current format.
+    pub fn parse(json: &str) -> Result<Self, MlsError> {
+        let state: Self = serde_json::from_value(upgrade(json)?)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid state JSON", &e))?;
+        check_storage_version(state.storage_version)?;
+        Ok(state)
+    }
+}
+
+impl WasmStateDelta {
+    pub fn new(storage: HashMap<String, String>, deleted: Vec<String>, signers: Vec<SignerEntry>) -> Self {
+        Self { version: STATE_FORMAT_VERSION, storage_version: CURRENT_VERSION, storage, deleted, signers }
+    }
+
+    /// Parse a delta written by this or any earlier build, migrating it to the current format.
+    pub fn parse(json: &str) -> Result<Self, MlsError> {
+        let delta: Self = serde_json::from_value(upgrade(json)?)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid state delta JSON", &e))?;
+        check_storage_version(delta.storage_version)?;
+        Ok(delta)
+    }
+}
+
+impl WasmGroupState {
+    pub fn new(group_id: String, storage: HashMap<String, String>, signers: Vec<SignerEntry>) -> Self {
+        Self { version: STATE_FORMAT_VERSION, storage_version: CURRENT_VERSION, group_id, storage, signers }
+    }
+
+    /// Parse a group export written by this or any earlier build (group exports start at format
+    /// version 2), migrating it to the current format.
+    pub fn parse(json: &str) -> Result<Self, MlsError> {
+        let group: Self = serde_json::from_value(upgrade(json)?)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid group state JSON", &e))?;
+        check_storage_version(group.storage_version)?;
+        Ok(group)
+    }
+}
+
+/// Parse exported JSON and migrate it step by step to `STATE_FORMAT_VERSION`.
+fn upgrade(json: &str) -> Result<Value, MlsError> {
+    let mut state: Value = serde_json::from_str(json)
+        .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid state JSON", &e))?;
+    if !state.is_object() {
+        return Err(MlsError::new(ErrorCode::InvalidInput, "State JSON must be an object"));
+    }
+
+    let version = match state.get("version") {
+        None => 1,
+        Some(version) => version.as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| MlsError::new(ErrorCode::InvalidInput, "Invalid state version"))?,
+    };
+    check_format_version(version)?;
+
+    if version < 2 {
+        migrate_v1(&mut state)?;
+    }
+    if version < 3 {
+        migrate_v2(&mut state)?;
+    }
+
+    Ok(state)
+}
+
+/// v1 -> v2: record versions and move the single legacy `signer` into `signers`.
+fn migrate_v1(state: &mut Value) -> Result<(), MlsError> {
+    let Some(fields) = state.as_object_mut() else {
+        return Err(MlsError::new(ErrorCode::InvalidInput, "State JSON must be an object"));
+    };
+
+    let mut signers: HashMap<u16, String> = match fields.remove("signers") {
+        Some(signers) => serde_json::from_value(signers)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid signers in state", &e))?,
+        None => HashMap::new(),
+    };
+    if let Some(Value::String(signer_json)) = fields.remove("signer") {
+        let signer: SignatureKeyPair = serde_json::from_str(&signer_json)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid signer JSON", &e))?;
+        signers.entry(signer.signature_scheme() as u16).or_insert(signer_json);
+    }
+
+    fields.insert("signers".into(), serde_json::json!(signers));
+    fields.insert("storage_version".into(), UNVERSIONED_STORAGE_VERSION.into());
+    fields.insert("version".into(), 2.into());
+    Ok(())
+}
+
+/// v2 -> v3: signers keyed by scheme become identity-less registry entries.
+fn migrate_v2(state: &mut Value) -> Result<(), MlsError> {
+    let Some(fields) = state.as_object_mut() else {
+        return Err(MlsError::new(ErrorCode::InvalidInput, "State JSON must be an object"));
+    };
+
+    let signers: HashMap<u16, String> = match fields.remove("signers") {
+        Some(signers) => serde_json::from_value(signers)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid signers in state", &e))?,
+        None => HashMap::new(),
+    };
+    let signers = SignerEntry::encode(signers.into_values().map(|signer| (None, signer)));
+
+    fields.insert("signers".into(), serde_json::json!(signers));
+    fields.insert("version".into(), 3.into());
+    Ok(())
+}
+
+fn check_format_version(version: u32) -> Result<(), MlsError> {
+    if version <= STATE_FORMAT_VERSION {
+        return Ok(());
+    }
+    Err(MlsError::with_variant(
+        ErrorCode::IncompatibleState,
+        "StateFormat::TooNew",
+        format!("State format version {} is newer than this build supports ({})", version, STATE_FORMAT_VERSION),
+    ))
+}
+
+/// OpenMLS storage keys embed the storage version and values are its serde layout, so a state
+/// from another storage version would only fail later in MlsGroup::load. No storage migrations
+/// exist yet: anything but the current version is rejected up front.
+fn check_storage_version(storage_version: u16) -> Result<(), MlsError> {
+    if storage_version == CURRENT_VERSION {
+        return Ok(());
+    }
+    Err(MlsError::with_variant(
+        ErrorCode::IncompatibleState,
+        "StateFormat::StorageVersion",
+        format!(
+            "State was written with OpenMLS storage version {}, this build reads version {}; it must be migrated first",
+            storage_version, CURRENT_VERSION
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openmls_traits::types::SignatureScheme;
+
+    fn signer_json() -> String {
+        serde_json::to_string(&SignatureKeyPair::new(SignatureScheme::ED25519).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrates_v1_single_signer() {
+        let signer = signer_json();
+        let v1 = serde_json::json!({ "storage": { "00": "01" }, "signer": signer }).to_string();
+        let state = WasmState::parse(&v1).unwrap();
+        assert_eq!(state.version, STATE_FORMAT_VERSION);
+        assert_eq!(state.storage_version, UNVERSIONED_STORAGE_VERSION);
+        assert_eq!(state.storage["00"], "01");
+        assert_eq!(state.signers.len(), 1);
+        assert_eq!(state.signers[0].identity, None);
+        assert_eq!(state.signers[0].signer, signer);
+    }
+
+    #[test]
+    fn migrates_v2_scheme_keyed_signers() {
+        let signer = signer_json();
+        let v2 = serde_json::json!({
+            "version": 2,
+            "storage_version": CURRENT_VERSION,
+            "storage": {},
+            "deleted": ["0a"],
+            "signers": { (SignatureScheme::ED25519 as u16).to_string(): signer },
+        }).to_string();
+        let delta = WasmStateDelta::parse(&v2).unwrap();
+        assert_eq!(delta.version, STATE_FORMAT_VERSION);
+        assert_eq!(delta.deleted, ["0a"]);
+        assert_eq!(delta.signers.len(), 1);
+        assert_eq!(delta.signers[0].identity, None);
+        assert_eq!(delta.signers[0].signer, signer);
+    }
+
+    #[test]
+    fn rejects_newer_format() {
+        let state = serde_json::json!({
+            "version": STATE_FORMAT_VERSION + 1,
+            "storage_version": CURRENT_VERSION,
+            "storage": {},
+            "signers": [],
+        }).to_string();
+        let err = WasmState::parse(&state).err().unwrap();
+        assert_eq!(err.code, ErrorCode::IncompatibleState);
+        assert_eq!(err.variant.as_deref(), Some("StateFormat::TooNew"));
+    }
+
+    #[test]
+    fn rejects_other_storage_version() {
+        let state = serde_json::json!({
+            "version": STATE_FORMAT_VERSION,
+            "storage_version": CURRENT_VERSION + 1,
+            "storage": {},
+            "signers": [],
+        }).to_string();
+        let err = WasmState::parse(&state).err().unwrap();
+        assert_eq!(err.code, ErrorCode::IncompatibleState);
+        assert_eq!(err.variant.as_deref(), Some("StateFormat::StorageVersion"));
+    }
+}
@@ -3,53 +3,230 @@
 // This storage persists for the duration of the WASM session
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use openmls::prelude::*;
 use openmls_basic_credential::SignatureKeyPair;
 
+use crate::error::{ErrorCode, MlsError};
+use crate::provider::{TrackingStorage, BACKEND, KEY_PACKAGE_LABEL};
+
 // Thread-local storage for MLS groups indexed by group_id
 thread_local! {
     pub static GROUPS: RefCell<HashMap<Vec<u8>, MlsGroup>> = RefCell::new(HashMap::new());
 }
 
-// Thread-local storage for KeyPackageBundle indexed by hash_ref
-thread_local! {
-    pub static KEY_PACKAGES: RefCell<HashMap<Vec<u8>, KeyPackageBundle>> = RefCell::new(HashMap::new());
+/// A signature keypair registered for one credential identity
+struct RegisteredSigner {
+    /// Credential identity the keypair signs for. `None` for signers restored from states
+    /// written before signers were keyed by identity; see get_or_create_signer.
+    identity: Option<Vec<u8>>,
+    scheme: u16,
+    public_key: Vec<u8>,
+    json: String,
+}
+
+impl RegisteredSigner {
+    fn parse(identity: Option<Vec<u8>>, json: String) -> Result<Self, MlsError> {
+        let signer: SignatureKeyPair = serde_json::from_str(&json)
+            .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid signer JSON", &e))?;
+        Ok(Self {
+            identity,
+            scheme: signer.signature_scheme() as u16,
+            public_key: signer.public().to_vec(),
+            json,
+        })
+    }
+
+    fn signer(&self) -> Result<SignatureKeyPair, MlsError> {
+        serde_json::from_str(&self.json)
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to deserialize signer", &e))
+    }
 }
 
-// Cached signer as JSON string for cross-session persistence.
+// Signer registry, one keypair per (credential identity, signature scheme) so several accounts
+// can share a WASM instance. Kept as JSON strings for cross-session persistence.
 // The same signer must be used across sessions because the group's leaf node
 // contains the signer's public key.
 thread_local! {
-    static SIGNER_JSON: RefCell<Option<String>> = RefCell::new(None);
+    static SIGNERS: RefCell<Vec<RegisteredSigner>> = const { RefCell::new(Vec::new()) };
 }
 
-/// Get or create a signature keypair for the given ciphersuite.
+/// Get or create the signature keypair of `identity` for the given ciphersuite's signature scheme.
 /// The keypair is cached in-session and serializable for cross-session persistence.
-pub fn get_or_create_signer(ciphersuite: Ciphersuite) -> Result<SignatureKeyPair, String> {
-    SIGNER_JSON.with(|sj| {
-        let mut opt = sj.borrow_mut();
-        if let Some(ref json) = *opt {
-            return serde_json::from_str(json)
-                .map_err(|e| format!("Failed to deserialize signer: {}", e));
+/// A signer restored without an identity (older state formats, which held one signer per
+/// scheme for the single account) that no stored group's leaf uses is bound to the first
+/// identity that asks for its scheme.
+pub fn get_or_create_signer(identity: &[u8], ciphersuite: Ciphersuite) -> Result<SignatureKeyPair, MlsError> {
+    let scheme = ciphersuite.signature_algorithm();
+    let has_unbound = SIGNERS.with(|s| s.borrow().iter().any(|r| r.scheme == scheme as u16 && r.identity.is_none()));
+    let leaf_keys = if has_unbound { bind_unbound_signers()? } else { HashSet::new() };
+
+    SIGNERS.with(|s| {
+        let mut signers = s.borrow_mut();
+        if let Some(registered) = signers.iter().find(|r| r.scheme == scheme as u16 && r.identity.as_deref() == Some(identity)) {
+            return registered.signer();
         }
-        let signer = SignatureKeyPair::new(ciphersuite.signature_algorithm())
-            .map_err(|e| format!("Failed to create signer: {:?}", e))?;
+        if let Some(registered) = signers.iter_mut()
+            .find(|r| r.scheme == scheme as u16 && r.identity.is_none() && !leaf_keys.contains(&r.public_key))
+        {
+            registered.identity = Some(identity.to_vec());
+            return registered.signer();
+        }
+        let signer = SignatureKeyPair::new(scheme)
+            .map_err(|e| MlsError::wrap(ErrorCode::CryptoError, "Failed to create signer", &e))?;
         let json = serde_json::to_string(&signer)
-            .map_err(|e| format!("Failed to serialize signer: {}", e))?;
-        *opt = Some(json);
+            .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Failed to serialize signer", &e))?;
+        signers.push(RegisteredSigner {
+            identity: Some(identity.to_vec()),
+            scheme: scheme as u16,
+            public_key: signer.public().to_vec(),
+            json,
+        });
         Ok(signer)
     })
 }
 
-/// Get the serialized signer JSON for persistence (None if no signer yet)
-pub fn get_signer_json() -> Option<String> {
-    SIGNER_JSON.with(|sj| sj.borrow().clone())
+/// Bind signers restored without an identity to the credential identity of the stored groups
+/// whose own leaf carries their public key, so another identity can never claim them.
+/// Call after restoring signers (import_state, apply_state_delta, import_group_state).
+/// Returns the signature keys of our leaves in all stored groups.
+pub fn bind_unbound_signers() -> Result<HashSet<Vec<u8>>, MlsError> {
+    if !SIGNERS.with(|s| s.borrow().iter().any(|r| r.identity.is_none())) {
+        return Ok(HashSet::new());
+    }
+
+    // (signature key, credential identity) of our leaf in each stored group
+    let own_leaves = BACKEND.with(|b| {
+        let backend = b.borrow();
+        let storage = backend.storage();
+        let group_ids: Vec<GroupId> = storage.stored_group_ids()
+            .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to list stored groups", &e))?;
+        let mut own_leaves: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::new();
+        for group_id in group_ids {
+            let group = MlsGroup::load(storage, &group_id)
+                .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to load group from storage", &e))?;
+            if let Some(leaf) = group.as_ref().and_then(|group| group.own_leaf_node()) {
+                let identity = BasicCredential::try_from(leaf.credential().clone())
+                    .ok()
+                    .map(|credential| credential.identity().to_vec());
+                own_leaves.push((leaf.signature_key().as_slice().to_vec(), identity));
+            }
+        }
+        Ok::<_, MlsError>(own_leaves)
+    })?;
+
+    SIGNERS.with(|s| {
+        for registered in s.borrow_mut().iter_mut().filter(|r| r.identity.is_none()) {
+            registered.identity = own_leaves.iter()
+                .find(|(public_key, _)| *public_key == registered.public_key)
+                .and_then(|(_, identity)| identity.clone());
+        }
+    });
+    Ok(own_leaves.into_iter().map(|(public_key, _)| public_key).collect())
+}
+
+/// The signer of our own leaf in `group`, found by the leaf's signature key.
+/// An unbound signer found this way is bound to the leaf's credential identity.
+pub fn group_signer(group: &MlsGroup) -> Result<SignatureKeyPair, MlsError> {
+    let own_leaf = group.own_leaf_node()
+        .ok_or_else(|| MlsError::new(ErrorCode::InvalidState, "Own leaf not found in group"))?;
+    let public_key = own_leaf.signature_key().as_slice();
+    SIGNERS.with(|s| {
+        let mut signers = s.borrow_mut();
+        let registered = signers.iter_mut()
+            .find(|r| r.public_key == public_key)
+            .ok_or_else(|| MlsError::new(ErrorCode::NotFound, "No signer registered for this group's leaf"))?;
+        if registered.identity.is_none() {
+            registered.identity = BasicCredential::try_from(own_leaf.credential().clone())
+                .ok()
+                .map(|credential| credential.identity().to_vec());
+        }
+        registered.signer()
+    })
+}
+
+/// Public key of the signer registered for `identity` and `scheme`, without creating one
+pub fn signer_public_key(identity: &[u8], scheme: SignatureScheme) -> Option<Vec<u8>> {
+    SIGNERS.with(|s| {
+        s.borrow().iter()
+            .find(|r| r.scheme == scheme as u16 && r.identity.as_deref() == Some(identity))
+            .map(|r| r.public_key.clone())
+    })
 }
 
-/// Restore the signer from a serialized JSON string (called during import_state)
-pub fn set_signer_json(json: String) {
-    SIGNER_JSON.with(|sj| *sj.borrow_mut() = Some(json));
+/// Get all serialized signers for persistence, with the identity each belongs to
+pub fn get_signer_jsons() -> Vec<(Option<Vec<u8>>, String)> {
+    SIGNERS.with(|s| s.borrow().iter().map(|r| (r.identity.clone(), r.json.clone())).collect())
+}
+
+/// The serialized signer whose public key is `public_key`, with its identity
+pub fn find_signer_json(public_key: &[u8]) -> Option<(Option<Vec<u8>>, String)> {
+    SIGNERS.with(|s| {
+        s.borrow().iter()
+            .find(|r| r.public_key == public_key)
+            .map(|r| (r.identity.clone(), r.json.clone()))
+    })
+}
+
+/// Restore a signer from a serialized JSON string (called during import_state).
+/// The signature scheme is read from the keypair itself; it replaces any signer held for the
+/// same identity and scheme.
+pub fn set_signer_json(identity: Option<Vec<u8>>, json: String) -> Result<(), MlsError> {
+    let new = RegisteredSigner::parse(identity, json)?;
+    SIGNERS.with(|s| {
+        let mut signers = s.borrow_mut();
+        // Already bound in this session: keep the binding
+        if new.identity.is_none() && signers.iter().any(|r| r.public_key == new.public_key) {
+            return;
+        }
+        signers.retain(|r| r.public_key != new.public_key && !(r.scheme == new.scheme && r.identity == new.identity));
+        signers.push(new);
+    });
+    Ok(())
+}
+
+/// Add a signer that arrived with an imported group. Fails if a different signer is already
+/// in use for its identity and signature scheme, since every group of that identity shares it.
+pub fn add_signer_json(identity: Option<Vec<u8>>, json: String) -> Result<(), MlsError> {
+    let new = RegisteredSigner::parse(identity, json)?;
+    let conflict = SIGNERS.with(|s| {
+        s.borrow().iter().any(|r| {
+            new.identity.is_some() && r.identity == new.identity && r.scheme == new.scheme && r.public_key != new.public_key
+        })
+    });
+    if conflict {
+        return Err(MlsError::new(ErrorCode::InvalidState, "A different signer is already in use for this identity and signature scheme"));
+    }
+    set_signer_json(new.identity, new.json)
+}
+
+/// Register a signer rebuilt from its raw private key (e.g. `mls_sk_enc` after decryption with
+/// K_enc) for `identity`. Fails with InvalidState if `identity` already has a different signer
+/// for the scheme, since its groups and key packages carry that signer's public key.
+/// `public_key` must come from derive_public_key.
+pub fn import_signer(identity: Vec<u8>, scheme: SignatureScheme, private_key: &[u8], public_key: Vec<u8>) -> Result<(), MlsError> {
+    let signer = SignatureKeyPair::from_raw(scheme, private_key.to_vec(), public_key);
+    let json = serde_json::to_string(&signer)
+        .map_err(|e| MlsError::wrap(ErrorCode::Internal, "Failed to serialize signer", &e))?;
+    add_signer_json(Some(identity), json)
+}
+
+/// Public key for a raw private key, encoded as openmls_rust_crypto generates it
+/// (Ed25519: 32 bytes; P-256: uncompressed SEC1 point)
+pub fn derive_public_key(scheme: SignatureScheme, private_key: &[u8]) -> Result<Vec<u8>, MlsError> {
+    match scheme {
+        SignatureScheme::ED25519 => {
+            let key = ed25519_dalek::SigningKey::try_from(private_key)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid Ed25519 private key", &e))?;
+            Ok(key.verifying_key().to_bytes().to_vec())
+        }
+        SignatureScheme::ECDSA_SECP256R1_SHA256 => {
+            let key = p256::SecretKey::from_slice(private_key)
+                .map_err(|e| MlsError::wrap(ErrorCode::InvalidInput, "Invalid P-256 private key", &e))?;
+            Ok(p256::elliptic_curve::sec1::ToEncodedPoint::to_encoded_point(&key.public_key(), false).as_bytes().to_vec())
+        }
+        _ => Err(MlsError::new(ErrorCode::InvalidInput, format!("Unsupported signature scheme: {:?}", scheme))),
+    }
 }
 
 /// Store a group in thread-local storage
@@ -59,18 +236,47 @@ pub fn store_group(group_id: Vec<u8>, group: MlsGroup) {
     });
 }
 
+/// Whether a group is loaded in this session's GROUPS map
+pub fn is_group_loaded(group_id: &[u8]) -> bool {
+    GROUPS.with(|g| g.borrow().contains_key(group_id))
+}
+
 /// Remove and return a group from thread-local storage
-/// This is used for operations that need mutable access (take → mutate → store back)
-pub fn take_group(group_id: &[u8]) -> Option<MlsGroup> {
-    GROUPS.with(|g| {
-        g.borrow_mut().remove(group_id)
-    })
+/// This is used for operations that need mutable access (take → mutate → store back).
+/// A group not in memory is loaded from backend storage, so callers need not load_group first.
+pub fn take_group(group_id: &[u8]) -> Result<Option<MlsGroup>, MlsError> {
+    if let Some(group) = GROUPS.with(|g| g.borrow_mut().remove(group_id)) {
+        return Ok(Some(group));
+    }
+    BACKEND.with(|b| MlsGroup::load(b.borrow().storage(), &GroupId::from_slice(group_id)))
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to load group from storage", &e))
 }
 
-/// Store a key package bundle in thread-local storage
-pub fn store_key_package(hash_ref: Vec<u8>, bundle: KeyPackageBundle) {
-    KEY_PACKAGES.with(|kp| {
-        kp.borrow_mut().insert(hash_ref, bundle);
-    });
+/// Drop a group from thread-local storage only; it stays in backend storage.
+/// Returns whether it was loaded.
+pub fn unload_group(group_id: &[u8]) -> bool {
+    GROUPS.with(|g| g.borrow_mut().remove(group_id).is_some())
 }
 
+/// All key package bundles in backend storage, with their storage keys.
+/// These are the packages not yet consumed by a Welcome (plus last-resort ones, which never are).
+pub fn stored_key_packages(storage: &TrackingStorage) -> Result<Vec<(Vec<u8>, KeyPackageBundle)>, MlsError> {
+    let values = storage.values.read()
+        .map_err(|_| MlsError::new(ErrorCode::StorageError, "Storage lock poisoned"))?;
+    values
+        .iter()
+        .filter(|(key, _)| key.starts_with(KEY_PACKAGE_LABEL))
+        .map(|(key, value)| {
+            let bundle = serde_json::from_slice(value)
+                .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to deserialize key package", &e))?;
+            Ok((key.clone(), bundle))
+        })
+        .collect()
+}
+
+/// Remove an entry from backend storage, zeroizing its bytes first
+/// (key package entries carry the HPKE init and encryption private keys).
+pub fn erase_storage_value(storage: &TrackingStorage, key: &[u8]) -> Result<(), MlsError> {
+    storage.erase(key)
+        .map_err(|e| MlsError::wrap(ErrorCode::StorageError, "Failed to erase storage value", &e))
+}
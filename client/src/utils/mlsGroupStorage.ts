@@ -1,11 +1,12 @@
 // MLS Group persistence in IndexedDB
-import type { MlsGroup } from '../mls/index';
+import type { MlsGroup, MlsStorageBackend } from '../mls/index';
 
 const DB_NAME = 'MlsChatGroups';
 const STORE_NAME = 'groups';
 const STATE_STORE = 'wasm_state';
 const SENT_MESSAGES_STORE = 'sent_messages';
-const DB_VERSION = 4; // v4: added sent_messages store
+const MLS_STORAGE_STORE = 'mls_storage';
+const DB_VERSION = 5; // v5: added mls_storage store
 
 export interface StoredMlsGroup extends MlsGroup {
   lastUpdated: number; // timestamp
@@ -47,6 +48,10 @@ async function openDB(): Promise<IDBDatabase> {
         // be shown in history after reload (MLS senders can't decrypt own ciphertext)
         db.createObjectStore(SENT_MESSAGES_STORE, { keyPath: 'id' });
       }
+      if (oldVersion < 5) {
+        // mls_storage store: WASM backend storage entries written through per key
+        db.createObjectStore(MLS_STORAGE_STORE, { keyPath: ['userId', 'key'] });
+      }
     };
   });
 }
@@ -238,3 +243,129 @@ export async function getCachedMessage(groupId: string, serverSeq: number): Prom
     request.onerror = () => reject(request.error);
   });
 }
+
+// Values of the write-through backend are sealed with AES-256-GCM under the deriveKWasmState key,
+// as iv || ciphertext, with the user ID and storage key as AAD so a value cannot be moved to
+// another key or account
+const storageAad = (userId: string, key: string) => new TextEncoder().encode(`${userId}:${key}`);
+
+async function sealValue(cryptoKey: CryptoKey, aad: Uint8Array, value: Uint8Array): Promise<Uint8Array> {
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const ciphertext = new Uint8Array(
+    await crypto.subtle.encrypt({ name: 'AES-GCM', iv, additionalData: aad }, cryptoKey, value)
+  );
+  const sealed = new Uint8Array(iv.length + ciphertext.length);
+  sealed.set(iv);
+  sealed.set(ciphertext, iv.length);
+  return sealed;
+}
+
+async function openValue(cryptoKey: CryptoKey, aad: Uint8Array, sealed: Uint8Array): Promise<Uint8Array> {
+  return new Uint8Array(await crypto.subtle.decrypt(
+    { name: 'AES-GCM', iv: sealed.slice(0, 12), additionalData: aad }, cryptoKey, sealed.slice(12)
+  ));
+}
+
+/**
+ * Open the write-through WASM storage backend for a user (pass to MlsClient.useStorage).
+ * `cryptoKey` is deriveKWasmState(prfOutput), the key of the encrypted wasm_state snapshot:
+ * values are encrypted at rest, while the storage keys (labels and group IDs) are not.
+ * WASM needs synchronous reads, so all entries are decrypted and preloaded into memory. Puts and
+ * deletes update the map at once and are buffered; the buffer is encrypted and committed to
+ * IndexedDB in a single transaction once the current task ends. A WASM call never yields, so
+ * every write of one MLS operation lands in the same transaction: a crash leaves either the old
+ * state or the new one, never new message secrets next to an old tree.
+ *
+ * Opt-in: App.tsx does not use it; it keeps the wasm_state snapshot path.
+ */
+export async function openMlsStorageBackend(
+  userId: string,
+  cryptoKey: CryptoKey,
+): Promise<MlsStorageBackend & { flush(): Promise<void> }> {
+  const db = await openDB();
+  const transaction = db.transaction([MLS_STORAGE_STORE], 'readonly');
+  const store = transaction.objectStore(MLS_STORAGE_STORE);
+  const request = store.getAll(IDBKeyRange.bound([userId, ''], [userId, '\uffff']));
+
+  const records: { key: string; value: Uint8Array }[] = await new Promise((resolve, reject) => {
+    request.onsuccess = () => resolve(request.result || []);
+    request.onerror = () => reject(request.error);
+  });
+
+  const toHex = (bytes: Uint8Array) => Array.from(bytes, b => b.toString(16).padStart(2, '0')).join('');
+  const fromHex = (hex: string) => new Uint8Array(hex.match(/../g)?.map(b => parseInt(b, 16)) ?? []);
+  // Fails (OperationError) for a different key, or a value moved to another storage key
+  const entries = new Map(await Promise.all(records.map(
+    async r => [r.key, await openValue(cryptoKey, storageAad(userId, r.key), r.value)] as const
+  )));
+
+  // Writes not yet handed to a flush: hex key -> new value, or null for a delete
+  let pending = new Map<string, Uint8Array | null>();
+  let scheduled = false;
+  // Flushes run one after another, so a newer value of a key is never overwritten by an older
+  // batch still being encrypted. Settles without rejecting.
+  let last: Promise<void> = Promise.resolve();
+
+  const write = async (batch: Map<string, Uint8Array | null>): Promise<void> => {
+    try {
+      // Encrypt before opening the transaction: it would commit while waiting on WebCrypto
+      const sealed = await Promise.all(Array.from(batch, async ([key, value]) =>
+        [key, value && await sealValue(cryptoKey, storageAad(userId, key), value)] as const
+      ));
+      const tx = db.transaction([MLS_STORAGE_STORE], 'readwrite');
+      const target = tx.objectStore(MLS_STORAGE_STORE);
+      for (const [key, value] of sealed) {
+        if (value) target.put({ userId, key, value });
+        else target.delete([userId, key]);
+      }
+      await new Promise<void>((resolve, reject) => {
+        tx.oncomplete = () => resolve();
+        tx.onabort = () => reject(tx.error);
+      });
+    } catch (error) {
+      // Nothing from the batch was written: retry it with the next flush unless superseded
+      for (const [key, value] of batch) {
+        if (!pending.has(key)) pending.set(key, value);
+      }
+      console.error('MLS storage write failed:', error);
+      throw error;
+    }
+  };
+
+  const flush = (): Promise<void> => {
+    if (pending.size === 0) return last;
+    const batch = pending;
+    pending = new Map();
+    const done = last.then(() => write(batch));
+    last = done.catch(() => {});
+    return done;
+  };
+
+  const queue = (key: string, value: Uint8Array | null) => {
+    pending.set(key, value);
+    if (!scheduled) {
+      scheduled = true;
+      queueMicrotask(() => {
+        scheduled = false;
+        flush().catch(() => {});
+      });
+    }
+  };
+
+  return {
+    keys: () => Array.from(entries.keys(), fromHex),
+    get: key => entries.get(toHex(key)),
+    put: (key, value) => {
+      const hex = toHex(key);
+      const copy = value.slice();
+      entries.set(hex, copy);
+      queue(hex, copy);
+    },
+    delete: key => {
+      const hex = toHex(key);
+      entries.delete(hex);
+      queue(hex, null);
+    },
+    flush,
+  };
+}